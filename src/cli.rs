@@ -2,18 +2,22 @@
 use {
     self::{
         config::{finalize_args, ConfigMerge, ProtoArgs},
-        deptree::DependencyTree,
+        deptree::{DependencyTable, DependencyTree},
     },
     crate::models::{
         assets::{ReadFrom, RegexOptions},
         block::{Delimiter, Guard},
-        error::Result,
+        column::{parse_format, Column},
+        detect::InputFormat,
         field::Field,
+        hash::HashAlgorithm,
+        pointer::{parse_pointer, PointerStyle},
+        record::RecordSeparator,
     },
     clap::{crate_authors, crate_version, App, Arg, ArgMatches as Matches, SubCommand},
     regex::Regex,
     simplelog::LevelFilter,
-    std::collections::{HashMap, HashSet},
+    std::{collections::HashSet, convert::TryFrom, sync::Arc},
 };
 
 mod config;
@@ -24,12 +28,28 @@ mod completions {
 }
 
 // Subset of all Field variants that can be used for valuable output
-const VALID_FIELDS: [Field; 5] = [
+const VALID_FIELDS: [Field; 21] = [
     Field::Identifier,
     Field::Pointer,
     Field::Type,
     Field::Value,
     Field::JmesPath,
+    Field::JsonPath,
+    Field::JqPath,
+    Field::Depth,
+    Field::Key,
+    Field::Parent,
+    Field::Index,
+    Field::Source,
+    Field::Line,
+    Field::Offset,
+    Field::Length,
+    Field::Row,
+    Field::Raw,
+    Field::Size,
+    Field::Len,
+    Field::Hash,
+    Field::SemType,
 ];
 
 pub fn generate_cli<'a, 'b>() -> App<'a, 'b> {
@@ -76,6 +96,110 @@ pub fn generate_cli<'a, 'b>() -> App<'a, 'b> {
             .help("Set stdin to read a JSON doc from each line")
             .long_help("Set stdin to read a JSON doc from each line, and begin processing at line <UNIT>")
         )
+        .arg(Arg::with_name("input_format")
+            .short("i")
+            .long("input_format")
+            .takes_value(true)
+            .value_name("FORMAT")
+            .possible_values(&["auto", "json", "ndjson", "concat", "rs", "array"])
+            .help("Set the format of the input(s), 'auto' detects it per input")
+            .long_help("Set the format of the input(s): a single JSON doc (json), a JSON doc per line (ndjson), concatenated JSON docs (concat), each value of which is identified by its position, RFC 7464 record separated JSON docs (rs) or a top-level array of records (array), each element of which is treated as its own JSON doc, as with '--split_array'. 'auto' detects the format of each input from its first buffer's worth of bytes. If not set, '--line' selects ndjson for stdin, and json is used otherwise")
+        )
+        .arg(Arg::with_name("jobs")
+            .short("j")
+            .long("jobs")
+            .takes_value(true)
+            .value_name("UINT")
+            .default_value("1")
+            .validator(|s| match s.parse::<usize>() {
+                Ok(0) => Err(format!("Number of jobs must be at least 1")),
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("Couldn't parse '{}' into a uint: {}", s, e))
+            })
+            .help("Number of inputs to process concurrently")
+            .long_help("Number of inputs to process concurrently, each with its own reader and builder. Output is written in input order, unless '--unordered' is set")
+        )
+        .arg(Arg::with_name("build_jobs")
+            .long("build_jobs")
+            .takes_value(true)
+            .value_name("UINT")
+            .default_value("1")
+            .validator(|s| match s.parse::<usize>() {
+                Ok(0) => Err(format!("Number of build jobs must be at least 1")),
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("Couldn't parse '{}' into a uint: {}", s, e))
+            })
+            .help("Number of threads building each line delimited input, or document split by '--split_depth'")
+            .long_help("Number of threads deserializing and building the lines of each line delimited (ndjson or rs) input, or the subtrees of each document split by '--split_depth'. Packets are built in parallel and re-sequenced, so output order is unchanged unless '--unordered' is set")
+        )
+        .arg(Arg::with_name("split_depth")
+            .long("split_depth")
+            .takes_value(true)
+            .value_name("UINT")
+            .validator(|s| match s.parse::<usize>() {
+                Ok(0) => Err(format!("Split depth must be at least 1")),
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("Couldn't parse '{}' into a uint: {}", s, e))
+            })
+            .help("Hand each subtree at depth UINT to the '--build_jobs' threads as a job of its own")
+            .long_help("Hand each subtree at depth UINT to the '--build_jobs' threads as a job of its own, its rows being re-sequenced into the usual order. For example, 1 builds each element of a root array or each value under a root object in parallel")
+        )
+        .arg(Arg::with_name("unordered")
+            .long("unordered")
+            .help("Write output as soon as it completes, instead of in input order")
+            .long_help("Write each input's output as soon as it completes, instead of in input order. Parallel builds (see '--build_jobs') are also written as they complete, instead of being re-sequenced")
+        )
+        .arg(Arg::with_name("split_array")
+            .long("split_array")
+            .conflicts_with("split_at")
+            .help("Treat each element of a top-level array as its own JSON doc")
+            .long_help("Treat each element of a top-level array as its own JSON doc, identified by its (1 based) position in the array. Shorthand for '--split_at \"\"'")
+        )
+        .arg(Arg::with_name("split_at")
+            .long("split_at")
+            .takes_value(true)
+            .value_name("POINTER")
+            .validator(|s| match parse_pointer(&s) {
+                Some(_) => Ok(()),
+                None => Err(format!("'{}' is not a JSON pointer, it must be empty or start with '/' (or '#/' as a URI fragment), escaping '~' and '/' in keys as '~0' and '~1'", s))
+            })
+            .help("Treat each element of the array at POINTER as its own JSON doc")
+            .long_help("Treat each element of the array at POINTER as its own JSON doc, identified by its (1 based) position in the array. Elements are streamed one at a time, with pointers rooted at the element. In line delimited and concatenated inputs, elements keep the ident of their line (or doc) instead, and their pointers start at their position in the array. Anything outside the array is skipped. POINTER may also be given as a URI fragment, e.g '#/a%20b'")
+        )
+        .arg(Arg::with_name("source_order")
+            .long("source_order")
+            .conflicts_with("split_depth")
+            .help("Write output in the order it appears in the source, containers before their children")
+            .long_help("Write output in the order it appears in the source (pre-order), each container before its children. Every doc (or element, see '--split_at') is held whole while it is built, instead of being unwound as it is read")
+        )
+        .arg(Arg::with_name("sort")
+            .long("sort")
+            .help("Sort output by identifier, then by json pointer")
+            .long_help("Sort output by identifier, then by json pointer, comparing array indices numerically (i.e '/items/2' before '/items/10'). Rows that tie keep the order they were built in. Output that outgrows 'config --buf_sort' is spilled to sorted runs in the temporary directory, and merged once all input is read")
+        )
+        .arg(Arg::with_name("uri_fragment")
+            .long("uri_fragment")
+            .help("Write json pointers as URI fragments, e.g '#/a%20b'")
+        )
+        .arg(Arg::with_name("hash")
+            .long("hash")
+            .takes_value(true)
+            .value_name("ALGO")
+            .possible_values(&["xxh3", "sha256"])
+            .default_value("xxh3")
+            .help("Set the algorithm used by the 'hash' field")
+            .long_help("Set the algorithm used by the 'hash' field: 64 bit XXH3 (xxh3), which is fast, or SHA-256 (sha256). Scalars are hashed as compact JSON, and objects and arrays over their children's digests with object keys sorted, so equal values hash the same whatever their formatting or key order")
+        )
+        .arg(Arg::with_name("number_types")
+            .long("number_types")
+            .help("Split the 'Number' type into 'Integer', 'Float' and 'BigInt'")
+            .long_help("Split the 'Number' type into 'Integer', 'Float' and 'BigInt', the latter being integers too large for 64 bits. Such integers are only told apart (and kept exact) when built with the 'exact-numbers' feature, otherwise they lose precision and are typed 'Float'")
+        )
+        .arg(Arg::with_name("value_size")
+            .long("value_size")
+            .help("Write the size of objects and arrays as their value")
+            .long_help("Write the number of members of an object, or elements of an array, as its value instead of leaving it empty. See also the 'size' field")
+        )
         .arg(
             Arg::with_name("delimiter")
                 .short("d")
@@ -101,6 +225,7 @@ pub fn generate_cli<'a, 'b>() -> App<'a, 'b> {
                     }
             )
             .help("Set field quote character")
+            .long_help("Set field quote character. Any occurrence of it inside a field (or header) is doubled, as CSV expects")
         )
         .arg(Arg::with_name("regex")
             .short("E")
@@ -151,15 +276,16 @@ pub fn generate_cli<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 // TODO: Figure out to pass &str made from Fields
                 .default_value("ident.jptr.type.value")
-                .validator(|fmt| {
-                    let res: Result<Vec<_>> = fmt.split('.').map(|sub| Field::try_from_whitelist(sub, &VALID_FIELDS).map(|_| ())
-                    ).collect();
-                    match res {
-                        Ok(_) => Ok(()),
-                        Err(e) => Err(format!("{}", e))
-                    }
+                .validator(|fmt| match parse_format(&fmt, &VALID_FIELDS) {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(format!("{}", e))
                 })
-                .help("A dot '.' separated list of fields describing how output is formatted [possible values: ident, jptr, type, value, jmes]")
+                .help("A dot '.' separated list of fields describing how output is formatted [possible values: ident, jptr, type, value, jmes, jsonpath, jq, depth, key, parent, index, source, line, offset, length, row, raw, size, len, hash, semtype, all]")
+                .long_help("A dot '.' (or comma ',') separated list of columns describing how output is formatted [possible values: ident, jptr, type, value, jmes, jsonpath, jq, depth, key, parent, index, source, line, offset, length, row, raw, size, len, hash, semtype, all]. 'all' expands to every field, and prefixing a field with '-' excludes it, e.g 'all.-jmes'. A quoted literal, e.g '\"prod\"', is written as is on every row. Suffixing a field or literal with ':alias' names its column in the '--header', e.g 'jptr:path'")
+        )
+        .arg(Arg::with_name("header")
+            .long("header")
+            .help("Write a header line naming each column, see '--format' for aliases")
         )
         .subcommand(
             SubCommand::with_name("config")
@@ -223,6 +349,65 @@ pub fn generate_cli<'a, 'b>() -> App<'a, 'b> {
                         )
                         .help("Input buffer size multiplied by value of '--factor'")
                 )
+                .arg(
+                    Arg::with_name("input_buffer_max")
+                        .long("buf_in_max")
+                        .value_name("UINT")
+                        .takes_value(true)
+                        .default_value("16384")
+                        .validator(|s| match s {
+                            ref s => match s.parse::<usize>() {
+                                Ok(_) => Ok(()),
+                                Err(e) => Err(format!("Couldn't parse '{}' into a uint: {}", s, e))
+                                }
+                            }
+                        )
+                        .help("Maximum size the line buffer may grow to, multiplied by value of '--factor'")
+                        .long_help("Maximum size the line buffer may grow to, multiplied by value of '--factor'. Lines larger than '--buf_in' grow the buffer up to this ceiling, lines larger than the ceiling are an error")
+                )
+                .arg(
+                    Arg::with_name("sort_buffer_size")
+                        .long("buf_sort")
+                        .value_name("UINT")
+                        .takes_value(true)
+                        .default_value("65536")
+                        .validator(|s| match s.parse::<usize>() {
+                            Ok(0) => Err(format!("Sort buffer size must be at least 1")),
+                            Ok(_) => Ok(()),
+                            Err(e) => Err(format!("Couldn't parse '{}' into a uint: {}", s, e))
+                        })
+                        .help("Memory used by '--sort' before spilling to disk, multiplied by value of '--factor'")
+                        .long_help("Memory used by '--sort' before spilling to disk, multiplied by value of '--factor'. Each time sorted output grows past this size it is written as a sorted run to a temporary file, and the runs are merged once all input is read")
+                )
+                .arg(
+                    Arg::with_name("batch_size")
+                        .long("batch_size")
+                        .value_name("UINT")
+                        .takes_value(true)
+                        .default_value("128")
+                        .validator(|s| match s.parse::<usize>() {
+                            Ok(0) => Err(format!("Batch size must be at least 1")),
+                            Ok(_) => Ok(()),
+                            Err(e) => Err(format!("Couldn't parse '{}' into a uint: {}", s, e))
+                        })
+                        .help("Number of items sent between threads at once")
+                        .long_help("Number of items sent between threads at once. Partial batches are sent at the end of each JSON doc")
+                )
+                .arg(
+                    Arg::with_name("channel_depth")
+                        .long("chan_depth")
+                        .value_name("UINT")
+                        .takes_value(true)
+                        .default_value("10")
+                        .validator(|s| match s {
+                            ref s => match s.parse::<usize>() {
+                                Ok(_) => Ok(()),
+                                Err(e) => Err(format!("Couldn't parse '{}' into a uint: {}", s, e))
+                                }
+                            }
+                        )
+                        .help("Number of batches that may wait between threads")
+                )
                 .arg(
                     Arg::with_name("byte_multiplier")
                         .long("factor")
@@ -236,18 +421,16 @@ pub fn generate_cli<'a, 'b>() -> App<'a, 'b> {
                 .arg(
                     Arg::with_name("eol_char_linereader")
                         .long("linereader_eol")
-                        .value_name("CHAR")
+                        .value_name("STRING")
                         .takes_value(true)
                         .default_value("\n")
                         .hide_default_value(true)
-                        .validator(|s| match s {
-                            ref s => match s.parse::<char>() {
-                                Ok(_) => Ok(()),
-                                Err(e) => Err(format!("Couldn't parse '{}' into a char: {}", s, e))
-                                }
-                            }
-                        )
-                        .help("Set stdin linereader's EOL character, ignored if '--lines' is not set")
+                        .validator(|s| match RecordSeparator::try_from(s.as_str()) {
+                            Ok(_) => Ok(()),
+                            Err(e) => Err(format!("Couldn't parse '{}' into a record separator: {}", s, e))
+                        })
+                        .help("Set stdin linereader's record separator, ignored if '--line' is not set")
+                        .long_help("Set stdin linereader's record separator, ignored if '--line' is not set. Accepts multiple bytes and the escapes: \\n, \\r, \\t, \\0, \\\\ and \\xHH... i.e '\\r\\n' or '\\x1e\\n'. If the separator is '\\n' (the default) any trailing '\\r' is also removed")
                 )
         )
         .subcommand(
@@ -276,15 +459,26 @@ pub fn generate_cli<'a, 'b>() -> App<'a, 'b> {
 
 #[derive(Debug)]
 pub struct ProgramArgs {
-    delimiter: Delimiter,
+    delimiter: Arc<Delimiter>,
     guard: Guard,
     debug_level: LevelFilter,
     by_line: (bool, usize),
+    input_format: Option<InputFormat>,
+    split_at: Option<Vec<String>>,
+    jobs: (usize, bool),
+    build_jobs: (usize, Option<usize>),
+    source_order: bool,
+    sort: bool,
+    value_size: bool,
+    number_types: bool,
+    hash_algorithm: HashAlgorithm,
+    pointer_style: PointerStyle,
     regex: Option<RegexOptions>,
-    format: Vec<Field>,
+    format: Vec<Column>,
+    header: bool,
     reader: Vec<Option<ReadFrom>>,
     writer: (Option<String>, bool),
-    dependency_map: HashMap<Field, bool>,
+    dependency_map: DependencyTable,
     subcommand_config: SubConfig,
 }
 
@@ -295,6 +489,11 @@ impl<'a, 'b> ProgramArgs {
         // Early exit if completions are called
         ProgramArgs::if_completions_exit(store.subcommand_matches("completions"));
 
+        ProgramArgs::from_store(store)
+    }
+
+    /// Builds the program's options from already parsed arguments
+    pub(crate) fn from_store(store: &Matches<'a>) -> Self {
         let mut proto = ProtoArgs::new(finalize_args::<&str>(&[]));
 
         let debug_level = proto.debug_level(store);
@@ -305,40 +504,72 @@ impl<'a, 'b> ProgramArgs {
 
         let format = proto.format(store);
 
+        let header = proto.header(store);
+
         let regex = proto.regex(store);
 
         let by_line = proto.by_line(store);
 
-        let delimiter = proto.delimiter(store);
+        let input_format = proto.input_format(store);
+
+        let split_at = proto.split_at(store);
+
+        let jobs = proto.jobs(store);
+
+        let build_jobs = proto.build_jobs(store);
+
+        let source_order = proto.source_order(store);
+
+        let sort = proto.sort(store);
+
+        let value_size = proto.value_size(store);
+
+        let number_types = proto.number_types(store);
+
+        let hash_algorithm = proto.hash_algorithm(store);
+
+        let pointer_style = proto.pointer_style(store);
+
+        let delimiter = Arc::new(proto.delimiter(store));
 
         let guard: Guard = proto.guard(store);
 
-        let dependency_map = DependencyTree::init().generate_list(match format.len() {
+        // Rows are only unwound for the fields they output,
+        // so literals alone still need one to repeat on every value
+        let literals_only = format.iter().all(|column| column.field().is_none());
+
+        let dependency_map = DependencyTree::init().generate_table(match format.len() {
             0 => unreachable!("Clap should validate output fields >= 1"),
             1 => format
                 .iter()
+                .filter_map(Column::field)
                 .chain(
                     [
                         Some(Field::Guard),
                         regex.as_ref().map(|regex| regex.on_field()),
+                        Some(Field::Type).filter(|_| literals_only),
+                        Some(Field::Identifier).filter(|_| sort),
+                        Some(Field::Pointer).filter(|_| sort),
                     ]
                     .iter()
-                    .filter_map(|i| i.as_ref()),
+                    .filter_map(|i| *i),
                 )
-                .copied()
                 .collect::<Vec<Field>>(),
             _ => format
                 .iter()
+                .filter_map(Column::field)
                 .chain(
                     [
                         Some(Field::Guard),
                         Some(Field::Delimiter),
                         regex.as_ref().map(|regex| regex.on_field()),
+                        Some(Field::Type).filter(|_| literals_only),
+                        Some(Field::Identifier).filter(|_| sort),
+                        Some(Field::Pointer).filter(|_| sort),
                     ]
                     .iter()
-                    .filter_map(|i| i.as_ref()),
+                    .filter_map(|i| *i),
                 )
-                .copied()
                 .collect::<Vec<Field>>(),
         });
 
@@ -350,8 +581,19 @@ impl<'a, 'b> ProgramArgs {
             guard,
             debug_level,
             by_line,
+            input_format,
+            split_at,
+            jobs,
+            build_jobs,
+            source_order,
+            sort,
+            value_size,
+            number_types,
+            hash_algorithm,
+            pointer_style,
             regex,
             format,
+            header,
             reader,
             writer,
             dependency_map,
@@ -379,30 +621,84 @@ impl<'a, 'b> ProgramArgs {
         self.by_line.1
     }
 
-    pub fn delimiter(&self) -> Delimiter {
-        self.delimiter.clone()
+    pub fn input_format(&self) -> Option<InputFormat> {
+        self.input_format
+    }
+
+    /// The path (as unescaped pointer segments) to the array
+    /// whose elements should each be treated as a JSON doc
+    pub fn split_at(&self) -> Option<&[String]> {
+        self.split_at.as_deref()
+    }
+
+    pub fn jobs(&self) -> usize {
+        self.jobs.0
+    }
+
+    pub fn unordered(&self) -> bool {
+        self.jobs.1
+    }
+
+    pub fn source_order(&self) -> bool {
+        self.source_order
+    }
+
+    pub fn sort(&self) -> bool {
+        self.sort
+    }
+
+    pub fn value_size(&self) -> bool {
+        self.value_size
+    }
+
+    pub fn number_types(&self) -> bool {
+        self.number_types
+    }
+
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    pub fn pointer_style(&self) -> PointerStyle {
+        self.pointer_style
+    }
+
+    pub fn build_jobs(&self) -> usize {
+        self.build_jobs.0
+    }
+
+    /// Depth at which documents are no longer unwound, and
+    /// instead sent whole to the builder
+    pub fn split_depth(&self) -> Option<usize> {
+        self.build_jobs.1
+    }
+
+    pub fn delimiter(&self) -> Arc<Delimiter> {
+        Arc::clone(&self.delimiter)
     }
 
     pub fn guard(&self) -> Guard {
         self.guard
     }
 
-    pub fn format(&self) -> &[Field] {
+    pub fn format(&self) -> &[Column] {
         &self.format
     }
 
+    pub fn header(&self) -> bool {
+        self.header
+    }
+
     pub fn regex(&self) -> Option<&RegexOptions> {
         self.regex.as_ref()
     }
 
     pub fn should_calculate<F: AsRef<Field>>(&self, field: F) -> bool {
-        self.dependency_map.contains_key(field.as_ref())
+        self.dependency_map[*field.as_ref() as usize].is_some()
     }
 
     pub fn should_store<F: AsRef<Field>>(&self, field: F) -> bool {
-        self.dependency_map
-            .get(field.as_ref())
-            .map_or(false, |b| *b)
+        self.dependency_map[*field.as_ref() as usize] == Some(true)
     }
 
     /// Checks if the 'completion' subcommand is active
@@ -477,8 +773,24 @@ impl<'a, 'b> ProgramArgs {
         self.subcommand_config.input_buffer_size
     }
 
-    pub fn linereader_eol(&self) -> u8 {
-        self.subcommand_config.linereader_eol
+    pub fn input_buffer_max(&self) -> usize {
+        self.subcommand_config.input_buffer_max
+    }
+
+    pub fn linereader_eol(&self) -> &RecordSeparator {
+        &self.subcommand_config.linereader_eol
+    }
+
+    pub fn sort_buffer_size(&self) -> usize {
+        self.subcommand_config.sort_buffer_size
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.subcommand_config.batch_size
+    }
+
+    pub fn channel_depth(&self) -> usize {
+        self.subcommand_config.channel_depth
     }
 }
 
@@ -488,7 +800,11 @@ struct SubConfig {
     max_handles: usize,
     output_buffer_size: usize,
     input_buffer_size: usize,
-    linereader_eol: u8,
+    input_buffer_max: usize,
+    linereader_eol: RecordSeparator,
+    sort_buffer_size: usize,
+    batch_size: usize,
+    channel_depth: usize,
 }
 
 impl SubConfig {
@@ -516,14 +832,22 @@ impl SubConfig {
                     .unwrap();
                 let output_buffer_size = proto.output_buffer_size(substore) * factor;
                 let input_buffer_size = proto.input_buffer_size(substore) * factor;
+                let input_buffer_max = proto.input_buffer_max(substore) * factor;
+                let sort_buffer_size = proto.sort_buffer_size(substore) * factor;
                 let linereader_eol = proto.linereader_eol(substore);
+                let batch_size = proto.batch_size(substore);
+                let channel_depth = proto.channel_depth(substore);
 
                 SubConfig {
                     logger,
                     max_handles,
                     output_buffer_size,
                     input_buffer_size,
+                    input_buffer_max,
+                    sort_buffer_size,
                     linereader_eol,
+                    batch_size,
+                    channel_depth,
                 }
             }
             None => Self::default(),
@@ -539,7 +863,11 @@ impl Default for SubConfig {
             max_handles: 1,
             output_buffer_size: 16 * 1024,
             input_buffer_size: 64 * 1024,
-            linereader_eol: b'\n',
+            input_buffer_max: 16 * 1024 * 1024,
+            sort_buffer_size: 64 * 1024 * 1024,
+            linereader_eol: RecordSeparator::default(),
+            batch_size: 128,
+            channel_depth: 10,
         }
     }
 }
@@ -604,6 +932,83 @@ mod tests {
         assert_eq!(app.unwrap_err().kind, ClapError::MissingRequiredArgument)
     }
 
+    #[test]
+    fn possible_opt_input_format() {
+        for format in &["auto", "json", "ndjson", "concat", "rs", "array"] {
+            let app = test_cli!().get_matches_from_safe(&["--input_format", format]);
+            assert!(app.is_ok());
+        }
+
+        let app = test_cli!().get_matches_from_safe(&["--input_format", "xml"]);
+        assert!(app.is_err());
+        assert_eq!(app.unwrap_err().kind, ClapError::InvalidValue)
+    }
+
+    #[test]
+    fn syntax_opt_split_at() {
+        for ptr in &["", "/", "/data/items", "/data/0", "/a~1b", "#/a%20b"] {
+            let app = test_cli!().get_matches_from_safe(&["--split_at", ptr]);
+            assert!(app.is_ok());
+        }
+
+        for ptr in &["data", "/a~2b", "#/a%2"] {
+            let app = test_cli!().get_matches_from_safe(&["--split_at", ptr]);
+            assert!(app.is_err());
+            assert_eq!(app.unwrap_err().kind, ClapError::ValueValidation)
+        }
+    }
+
+    #[test]
+    fn conflict_split_array_split_at() {
+        let app = test_cli!().get_matches_from_safe(&["--split_array", "--split_at", "/data"]);
+        assert!(app.is_err());
+        assert_eq!(app.unwrap_err().kind, ClapError::ArgumentConflict)
+    }
+
+    #[test]
+    fn conflict_source_order_split_depth() {
+        let app = test_cli!().get_matches_from_safe(&["--source_order", "--split_depth", "1"]);
+        assert!(app.is_err());
+        assert_eq!(app.unwrap_err().kind, ClapError::ArgumentConflict)
+    }
+
+    #[test]
+    fn validate_opt_jobs_success() {
+        let app = test_cli!().get_matches_from_safe(&["--jobs", "4", "--unordered"]);
+        assert!(app.is_ok());
+    }
+
+    #[test]
+    fn validate_opt_jobs_failure() {
+        for jobs in &["0", "not a usize"] {
+            let app = test_cli!().get_matches_from_safe(&["--jobs", jobs]);
+            assert!(app.is_err());
+            assert_eq!(app.unwrap_err().kind, ClapError::ValueValidation)
+        }
+    }
+
+    #[test]
+    fn validate_opt_build_jobs() {
+        let app = test_cli!().get_matches_from_safe(&["--build_jobs", "8"]);
+        assert!(app.is_ok());
+
+        let app = test_cli!().get_matches_from_safe(&["--build_jobs", "0"]);
+        assert!(app.is_err());
+        assert_eq!(app.unwrap_err().kind, ClapError::ValueValidation)
+    }
+
+    #[test]
+    fn validate_opt_split_depth() {
+        let app = test_cli!().get_matches_from_safe(&["--build_jobs", "4", "--split_depth", "1"]);
+        assert!(app.is_ok());
+
+        for depth in &["0", "not a usize"] {
+            let app = test_cli!().get_matches_from_safe(&["--split_depth", depth]);
+            assert!(app.is_err());
+            assert_eq!(app.unwrap_err().kind, ClapError::ValueValidation)
+        }
+    }
+
     #[test]
     fn syntax_arg_input() {
         let app = test_cli!().get_matches_from_safe(&["path1", "path2", "-", "path4"]);
@@ -661,6 +1066,20 @@ mod tests {
         assert_eq!(app.unwrap_err().kind, ClapError::ValueValidation);
     }
 
+    #[test]
+    fn literal_format_unwinds_values() {
+        for fmt in &["'x'", "'x'.'y'"] {
+            let store = test_cli!().get_matches_from(&["--format", fmt]);
+            let opts = ProgramArgs::from_store(&store);
+
+            assert!(opts.should_calculate(Field::Type));
+            assert!(opts.should_calculate(Field::Value));
+        }
+        let store = test_cli!().get_matches_from(&["--format", "'x'.jptr"]);
+
+        assert!(!ProgramArgs::from_store(&store).should_calculate(Field::Type));
+    }
+
     #[test]
     fn syntax_subcommand_opt_log_to() {
         let app =
@@ -745,6 +1164,57 @@ mod tests {
         assert_eq!(app.unwrap_err().kind, ClapError::ValueValidation)
     }
 
+    #[test]
+    fn validate_subcommand_opt_batch_size_success() {
+        let app = test_cli!().get_matches_from_safe(&[
+            "config",
+            "--batch_size",
+            "64",
+            "--chan_depth",
+            "0",
+        ]);
+        assert!(app.is_ok());
+    }
+
+    #[test]
+    fn validate_subcommand_opt_batch_size_failure() {
+        for args in &[
+            ["config", "--batch_size", "0"],
+            ["config", "--chan_depth", "not a usize"],
+        ] {
+            let app = test_cli!().get_matches_from_safe(args);
+            assert!(app.is_err());
+            assert_eq!(app.unwrap_err().kind, ClapError::ValueValidation)
+        }
+    }
+
+    #[test]
+    fn validate_subcommand_opt_buf_sort() {
+        let app = test_cli!().get_matches_from_safe(&["config", "--buf_sort", "1024"]);
+        assert!(app.is_ok());
+
+        for size in &["0", "not a usize"] {
+            let app = test_cli!().get_matches_from_safe(&["config", "--buf_sort", size]);
+            assert!(app.is_err());
+            assert_eq!(app.unwrap_err().kind, ClapError::ValueValidation)
+        }
+    }
+
+    #[test]
+    fn validate_subcommand_opt_buf_in_max_success() {
+        let app = test_cli!().get_matches_from_safe(&["config", "--buf_in_max", "5"]);
+
+        assert!(app.is_ok())
+    }
+
+    #[test]
+    fn validate_subcommand_opt_buf_in_max_failure() {
+        let app = test_cli!().get_matches_from_safe(&["config", "--buf_in_max", "not a usize"]);
+
+        assert!(app.is_err());
+        assert_eq!(app.unwrap_err().kind, ClapError::ValueValidation)
+    }
+
     #[test]
     fn validate_subcommand_opt_linereader_eol_success() {
         let app = test_cli!().get_matches_from_safe(&["config", "--linereader_eol", ":"]);
@@ -752,9 +1222,17 @@ mod tests {
         assert!(app.is_ok())
     }
 
+    #[test]
+    fn validate_subcommand_opt_linereader_eol_multi_byte() {
+        for sep in &["\\r\\n", "\\x1e\\n", "||"] {
+            let app = test_cli!().get_matches_from_safe(&["config", "--linereader_eol", sep]);
+            assert!(app.is_ok());
+        }
+    }
+
     #[test]
     fn validate_subcommand_opt_linereader_eol_failure() {
-        let app = test_cli!().get_matches_from_safe(&["config", "--linereader_eol", "not a char"]);
+        let app = test_cli!().get_matches_from_safe(&["config", "--linereader_eol", "\\q"]);
 
         assert!(app.is_err());
         assert_eq!(app.unwrap_err().kind, ClapError::ValueValidation)
@@ -767,4 +1245,26 @@ mod tests {
             assert!(app.is_ok());
         }
     }
+
+    #[test]
+    fn dependencies_resolve_transitively() {
+        let list = DependencyTree::init().generate_list(&[Field::JqPath]);
+
+        assert_eq!(list.get(&Field::JqPath), Some(&true));
+        assert_eq!(list.get(&Field::Pointer), Some(&false));
+        assert_eq!(list.get(&Field::Value), Some(&false));
+        assert_eq!(list.get(&Field::Guard), Some(&false));
+        assert_eq!(list.get(&Field::Type), None);
+    }
+
+    #[test]
+    fn dependency_table_matches_list() {
+        let tree = DependencyTree::init();
+        let list = tree.generate_list(&[Field::Depth, Field::Identifier]);
+        let table = tree.generate_table(&[Field::Depth, Field::Identifier]);
+
+        for field in &VALID_FIELDS {
+            assert_eq!(table[*field as usize], list.get(field).copied());
+        }
+    }
 }
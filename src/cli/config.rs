@@ -1,14 +1,19 @@
 use {
     super::config::env::{Env, EnvArgs},
+    crate::cli::VALID_FIELDS,
     crate::models::{
         assets::{ReadFrom, RegexOptions},
         block::{Delimiter, Guard},
-        field::Field,
+        column::{parse_format, Column},
+        detect::InputFormat,
         get_reader,
+        hash::HashAlgorithm,
+        pointer::{parse_pointer, PointerStyle},
+        record::RecordSeparator,
     },
     clap::ArgMatches,
     simplelog::LevelFilter,
-    std::path::Path,
+    std::{convert::TryFrom, path::Path},
 };
 
 pub(in crate::cli) use merge::ConfigMerge;
@@ -113,19 +118,24 @@ where
         }
     }
 
-    pub(in crate::cli) fn format(&mut self, store: &ArgMatches<'_>) -> Vec<Field> {
+    pub(in crate::cli) fn format(&mut self, store: &ArgMatches<'_>) -> Vec<Column> {
+        // Unwraps validated by clap
         match (store.occurrences_of("format"), store.value_of("format")) {
             (0, Some(fmt)) => {
                 let format = self.config.format();
 
-                || -> Option<Vec<Field>> { format?.ok() }()
-                    .unwrap_or_else(|| fmt.split('.').map(Field::from).collect())
+                || -> Option<Vec<Column>> { format?.ok() }()
+                    .unwrap_or_else(|| parse_format(fmt, &VALID_FIELDS).unwrap())
             }
-            (_, Some(fmt)) => fmt.split('.').map(Field::from).collect(),
+            (_, Some(fmt)) => parse_format(fmt, &VALID_FIELDS).unwrap(),
             (_, _) => unreachable!("Default format should be set by clap"),
         }
     }
 
+    pub(in crate::cli) fn header(&mut self, store: &ArgMatches<'_>) -> bool {
+        store.is_present("header")
+    }
+
     pub(in crate::cli) fn regex(&mut self, store: &ArgMatches<'_>) -> Option<RegexOptions> {
         match (store.value_of("regex"), store.value_of("regex_column")) {
             (Some(pattern), Some(column)) => Some(RegexOptions::new(pattern, column.into())),
@@ -149,6 +159,72 @@ where
         }
     }
 
+    pub(in crate::cli) fn input_format(&mut self, store: &ArgMatches<'_>) -> Option<InputFormat> {
+        store.value_of("input_format").map(InputFormat::from)
+    }
+
+    pub(in crate::cli) fn split_at(&mut self, store: &ArgMatches<'_>) -> Option<Vec<String>> {
+        match (store.is_present("split_array"), store.value_of("split_at")) {
+            (true, _) | (false, Some("")) => Some(Vec::new()),
+            // Validated by clap
+            (false, Some(ptr)) => parse_pointer(ptr),
+            (false, None) => None,
+        }
+    }
+
+    pub(in crate::cli) fn source_order(&mut self, store: &ArgMatches<'_>) -> bool {
+        store.is_present("source_order")
+    }
+
+    pub(in crate::cli) fn sort(&mut self, store: &ArgMatches<'_>) -> bool {
+        store.is_present("sort")
+    }
+
+    pub(in crate::cli) fn value_size(&mut self, store: &ArgMatches<'_>) -> bool {
+        store.is_present("value_size")
+    }
+
+    pub(in crate::cli) fn number_types(&mut self, store: &ArgMatches<'_>) -> bool {
+        store.is_present("number_types")
+    }
+
+    pub(in crate::cli) fn hash_algorithm(&mut self, store: &ArgMatches<'_>) -> HashAlgorithm {
+        // Default set by clap
+        store
+            .value_of("hash")
+            .map(HashAlgorithm::from)
+            .unwrap_or_default()
+    }
+
+    pub(in crate::cli) fn pointer_style(&mut self, store: &ArgMatches<'_>) -> PointerStyle {
+        match store.is_present("uri_fragment") {
+            true => PointerStyle::Fragment,
+            false => PointerStyle::Plain,
+        }
+    }
+
+    pub(in crate::cli) fn jobs(&mut self, store: &ArgMatches<'_>) -> (usize, bool) {
+        (
+            // Unwrap validated by clap
+            store.value_of("jobs").unwrap().parse::<usize>().unwrap(),
+            store.is_present("unordered"),
+        )
+    }
+
+    pub(in crate::cli) fn build_jobs(&mut self, store: &ArgMatches<'_>) -> (usize, Option<usize>) {
+        // Unwraps validated by clap
+        (
+            store
+                .value_of("build_jobs")
+                .unwrap()
+                .parse::<usize>()
+                .unwrap(),
+            store
+                .value_of("split_depth")
+                .map(|depth| depth.parse::<usize>().unwrap()),
+        )
+    }
+
     pub(in crate::cli) fn delimiter(&mut self, store: &ArgMatches<'_>) -> Delimiter {
         match (
             store.occurrences_of("delimiter"),
@@ -209,17 +285,77 @@ where
         }
     }
 
-    pub(in crate::cli) fn linereader_eol(&mut self, substore: &ArgMatches<'_>) -> u8 {
+    pub(in crate::cli) fn input_buffer_max(&mut self, substore: &ArgMatches<'_>) -> usize {
+        match (
+            substore.occurrences_of("input_buffer_max"),
+            substore.value_of("input_buffer_max"),
+            self.config.input_buffer_max(),
+        ) {
+            (0, _, Some(u)) => u,
+            // Unwrap validated by clap
+            (0, Some(s), None) => s.parse::<usize>().unwrap(),
+            // Unwrap validated by clap
+            (_, Some(s), _) => s.parse::<usize>().unwrap(),
+            (_, _, _) => unreachable!("Default input_buffer_max should be set by clap"),
+        }
+    }
+
+    pub(in crate::cli) fn sort_buffer_size(&mut self, substore: &ArgMatches<'_>) -> usize {
+        match (
+            substore.occurrences_of("sort_buffer_size"),
+            substore.value_of("sort_buffer_size"),
+            self.config.sort_buffer_size(),
+        ) {
+            (0, _, Some(u)) => u,
+            // Unwrap validated by clap
+            (0, Some(s), None) => s.parse::<usize>().unwrap(),
+            // Unwrap validated by clap
+            (_, Some(s), _) => s.parse::<usize>().unwrap(),
+            (_, _, _) => unreachable!("Default sort_buffer_size should be set by clap"),
+        }
+    }
+
+    pub(in crate::cli) fn batch_size(&mut self, substore: &ArgMatches<'_>) -> usize {
+        match (
+            substore.occurrences_of("batch_size"),
+            substore.value_of("batch_size"),
+            self.config.batch_size(),
+        ) {
+            (0, _, Some(u)) => u,
+            // Unwrap validated by clap
+            (0, Some(s), None) => s.parse::<usize>().unwrap(),
+            // Unwrap validated by clap
+            (_, Some(s), _) => s.parse::<usize>().unwrap(),
+            (_, _, _) => unreachable!("Default batch_size should be set by clap"),
+        }
+    }
+
+    pub(in crate::cli) fn channel_depth(&mut self, substore: &ArgMatches<'_>) -> usize {
+        match (
+            substore.occurrences_of("channel_depth"),
+            substore.value_of("channel_depth"),
+            self.config.channel_depth(),
+        ) {
+            (0, _, Some(u)) => u,
+            // Unwrap validated by clap
+            (0, Some(s), None) => s.parse::<usize>().unwrap(),
+            // Unwrap validated by clap
+            (_, Some(s), _) => s.parse::<usize>().unwrap(),
+            (_, _, _) => unreachable!("Default channel_depth should be set by clap"),
+        }
+    }
+
+    pub(in crate::cli) fn linereader_eol(&mut self, substore: &ArgMatches<'_>) -> RecordSeparator {
         match (
             substore.occurrences_of("eol_char_linereader"),
             substore.value_of("eol_char_linereader"),
             self.config.linereader_eol(),
         ) {
-            (0, _, Some(c)) => c as u8,
+            (0, _, Some(sep)) => sep,
             // Unwrap validated by clap
-            (0, Some(s), None) => s.parse::<char>().unwrap() as u8,
+            (0, Some(s), None) => RecordSeparator::try_from(s).unwrap(),
             // Unwrap validated by clap
-            (_, Some(s), _) => s.parse::<char>().unwrap() as u8,
+            (_, Some(s), _) => RecordSeparator::try_from(s).unwrap(),
             (_, _, _) => unreachable!("Default linereader_eol should be set by clap"),
         }
     }
@@ -249,17 +385,22 @@ where
 mod args {
     use crate::models::{
         block::{Delimiter, Guard},
+        column::Column,
         error::Result,
-        field::Field,
+        record::RecordSeparator,
     };
     pub(super) type OptDelim = Option<Delimiter>;
     pub(super) type OptGuard = Option<Guard>;
     pub(super) type OptDebug = Option<usize>;
     pub(super) type OptLine = Option<usize>;
-    pub(super) type OptFormat = Option<Result<Vec<Field>>>;
+    pub(super) type OptFormat = Option<Result<Vec<Column>>>;
     pub(super) type OptBufIn = Option<usize>;
+    pub(super) type OptBufInMax = Option<usize>;
     pub(super) type OptBufOut = Option<usize>;
-    pub(super) type OptEOL = Option<char>;
+    pub(super) type OptBufSort = Option<usize>;
+    pub(super) type OptEOL = Option<RecordSeparator>;
+    pub(super) type OptBatchSize = Option<usize>;
+    pub(super) type OptChanDepth = Option<usize>;
     pub(super) type OptQuiet = Option<bool>;
     pub(super) type OptAppend = Option<bool>;
     pub(super) type OptFactor = Option<usize>;
@@ -272,7 +413,7 @@ mod tests {
             env::{Env, Kind, Mock},
             *,
         },
-        crate::cli::generate_cli,
+        crate::{cli::generate_cli, models::field::Field},
         clap::AppSettings,
         simplelog::LevelFilter,
         std::{error, result},
@@ -343,6 +484,28 @@ mod tests {
         };
     }
 
+    #[test]
+    fn proto_split_at() -> Result<()> {
+        let mut proto = mock!(file "");
+
+        assert_eq!(proto.split_at(&cli!()?), None);
+        assert_eq!(proto.split_at(&cli!("--split_array")?), Some(vec![]));
+        assert_eq!(proto.split_at(&cli!("--split_at", "")?), Some(vec![]));
+        assert_eq!(
+            proto.split_at(&cli!("--split_at", "/data/items")?),
+            Some(vec![String::from("data"), String::from("items")])
+        );
+        assert_eq!(
+            proto.split_at(&cli!("--split_at", "/")?),
+            Some(vec![String::new()])
+        );
+        assert_eq!(
+            proto.split_at(&cli!("--split_at", "#/a~1b/c%20d")?),
+            Some(vec![String::from("a/b"), String::from("c d")])
+        );
+        Ok(())
+    }
+
     #[test]
     fn merge_debug_level_cli() -> Result<()> {
         let cli = cli!("-v")?;
@@ -432,7 +595,7 @@ mod tests {
         let cli = cli!("--format", "ident")?;
         let mut proto = mock!(env Kind::Format, "jptr" ;file "format = 'type'");
 
-        assert_eq!(proto.format(&cli), vec![Field::Identifier]);
+        assert_eq!(proto.format(&cli), vec![Field::Identifier.into()]);
         Ok(())
     }
 
@@ -441,7 +604,7 @@ mod tests {
         let cli = cli!()?;
         let mut proto = mock!(env Kind::Format, "jptr" ;file "format = 'type'");
 
-        assert_eq!(proto.format(&cli), vec![Field::Pointer]);
+        assert_eq!(proto.format(&cli), vec![Field::Pointer.into()]);
         Ok(())
     }
 
@@ -451,7 +614,7 @@ mod tests {
         let cli = cli!()?;
         let mut proto = mock!(file "format = 'type'");
 
-        assert_eq!(proto.format(&cli), vec![Field::Type]);
+        assert_eq!(proto.format(&cli), vec![Field::Type.into()]);
         Ok(())
     }
 
@@ -631,6 +794,154 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn merge_batch_size_cli() -> Result<()> {
+        let cli = cli!("config", "--batch_size", "64")?;
+        let mut proto = mock!(env Kind::BatchSize, "32" ;file "batch_size = 16");
+
+        assert_eq!(
+            proto.batch_size(&cli.subcommand_matches("config").unwrap()),
+            64
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_batch_size_env() -> Result<()> {
+        let cli = cli!("config")?;
+        let mut proto = mock!(env Kind::BatchSize, "32" ;file "batch_size = 16");
+
+        assert_eq!(
+            proto.batch_size(&cli.subcommand_matches("config").unwrap()),
+            32
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "config-file")]
+    fn merge_batch_size_file() -> Result<()> {
+        let cli = cli!("config")?;
+        let mut proto = mock!(file "batch_size = 16" => "config");
+
+        assert_eq!(
+            proto.batch_size(&cli.subcommand_matches("config").unwrap()),
+            16
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_channel_depth_cli() -> Result<()> {
+        let cli = cli!("config", "--chan_depth", "4")?;
+        let mut proto = mock!(env Kind::ChanDepth, "2" ;file "chan_depth = 1");
+
+        assert_eq!(
+            proto.channel_depth(&cli.subcommand_matches("config").unwrap()),
+            4
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_channel_depth_env() -> Result<()> {
+        let cli = cli!("config")?;
+        let mut proto = mock!(env Kind::ChanDepth, "2" ;file "chan_depth = 1");
+
+        assert_eq!(
+            proto.channel_depth(&cli.subcommand_matches("config").unwrap()),
+            2
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "config-file")]
+    fn merge_channel_depth_file() -> Result<()> {
+        let cli = cli!("config")?;
+        let mut proto = mock!(file "chan_depth = 1" => "config");
+
+        assert_eq!(
+            proto.channel_depth(&cli.subcommand_matches("config").unwrap()),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_input_buffer_max_cli() -> Result<()> {
+        let cli = cli!("config", "--buf_in_max", "128")?;
+        let mut proto = mock!(env Kind::BufInMax, "64" ;file "buf_in_max = 32");
+
+        assert_eq!(
+            proto.input_buffer_max(&cli.subcommand_matches("config").unwrap()),
+            128
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_input_buffer_max_env() -> Result<()> {
+        let cli = cli!("config")?;
+        let mut proto = mock!(env Kind::BufInMax, "64" ;file "buf_in_max = 32");
+
+        assert_eq!(
+            proto.input_buffer_max(&cli.subcommand_matches("config").unwrap()),
+            64
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "config-file")]
+    fn merge_input_buffer_max_file() -> Result<()> {
+        let cli = cli!("config")?;
+        let mut proto = mock!(file "buf_in_max = 32" => "config");
+
+        assert_eq!(
+            proto.input_buffer_max(&cli.subcommand_matches("config").unwrap()),
+            32
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_sort_buffer_size_cli() -> Result<()> {
+        let cli = cli!("config", "--buf_sort", "128")?;
+        let mut proto = mock!(env Kind::BufSort, "64" ;file "buf_sort = 32");
+
+        assert_eq!(
+            proto.sort_buffer_size(&cli.subcommand_matches("config").unwrap()),
+            128
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_sort_buffer_size_env() -> Result<()> {
+        let cli = cli!("config")?;
+        let mut proto = mock!(env Kind::BufSort, "64" ;file "buf_sort = 32");
+
+        assert_eq!(
+            proto.sort_buffer_size(&cli.subcommand_matches("config").unwrap()),
+            64
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "config-file")]
+    fn merge_sort_buffer_size_file() -> Result<()> {
+        let cli = cli!("config")?;
+        let mut proto = mock!(file "buf_sort = 32" => "config");
+
+        assert_eq!(
+            proto.sort_buffer_size(&cli.subcommand_matches("config").unwrap()),
+            32
+        );
+        Ok(())
+    }
+
     #[test]
     fn merge_linereader_eol_cli() -> Result<()> {
         let cli = cli!("config", "--linereader_eol", ":")?;
@@ -638,7 +949,7 @@ mod tests {
 
         assert_eq!(
             proto.linereader_eol(&cli.subcommand_matches("config").unwrap()),
-            b':'
+            RecordSeparator::try_from(":")?
         );
         Ok(())
     }
@@ -650,7 +961,7 @@ mod tests {
 
         assert_eq!(
             proto.linereader_eol(&cli.subcommand_matches("config").unwrap()),
-            b'='
+            RecordSeparator::try_from("=")?
         );
         Ok(())
     }
@@ -663,7 +974,21 @@ mod tests {
 
         assert_eq!(
             proto.linereader_eol(&cli.subcommand_matches("config").unwrap()),
-            b'-'
+            RecordSeparator::try_from("-")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_linereader_eol_multi_byte() -> Result<()> {
+        let cli = cli!("config", "--linereader_eol", "\\r\\n")?;
+        let mut proto = mock!(env Kind::RdrEol, "\\x1e" ;file "linereader_eol = '-'");
+
+        assert_eq!(
+            proto
+                .linereader_eol(&cli.subcommand_matches("config").unwrap())
+                .as_bytes(),
+            b"\r\n"
         );
         Ok(())
     }
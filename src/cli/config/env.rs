@@ -4,13 +4,15 @@ use {
         cli::VALID_FIELDS,
         models::{
             block::{Delimiter, Guard},
+            column::{parse_format, Column},
             error::Result,
-            field::Field,
+            record::RecordSeparator,
         },
         with_log,
     },
     std::{
         collections::HashMap,
+        convert::TryFrom,
         env::{var as get_env, VarError},
         ffi::OsStr,
         iter::FromIterator,
@@ -19,7 +21,7 @@ use {
 };
 
 /// List of possible variables
-const ENVIRONMENT_VARIABLES: [&str; 11] = [
+const ENVIRONMENT_VARIABLES: [&str; 15] = [
     "JAESVE_DEBUG",
     "JAESVE_QUIET",
     "JAESVE_APPEND",
@@ -29,7 +31,11 @@ const ENVIRONMENT_VARIABLES: [&str; 11] = [
     "JAESVE_FORMAT",
     "JAESVE_BUF_OUT",
     "JAESVE_BUF_IN",
+    "JAESVE_BUF_IN_MAX",
+    "JAESVE_BUF_SORT",
     "JAESVE_LINEREADER_EOL",
+    "JAESVE_BATCH_SIZE",
+    "JAESVE_CHAN_DEPTH",
     "JAESVE_FACTOR",
 ];
 
@@ -45,7 +51,11 @@ pub(super) enum Kind {
     Format,
     BufIn,
     BufOut,
+    BufInMax,
+    BufSort,
     RdrEol,
+    BatchSize,
+    ChanDepth,
     Factor,
 }
 
@@ -61,7 +71,11 @@ impl<S: AsRef<str>> From<S> for Kind {
             "JAESVE_FORMAT" => Kind::Format,
             "JAESVE_BUF_OUT" => Kind::BufIn,
             "JAESVE_BUF_IN" => Kind::BufOut,
+            "JAESVE_BUF_IN_MAX" => Kind::BufInMax,
+            "JAESVE_BUF_SORT" => Kind::BufSort,
             "JAESVE_LINEREADER_EOL" => Kind::RdrEol,
+            "JAESVE_BATCH_SIZE" => Kind::BatchSize,
+            "JAESVE_CHAN_DEPTH" => Kind::ChanDepth,
             "JAESVE_FACTOR" => Kind::Factor,
             _ => panic!("Tried to convert bad &str to env Kind"),
         }
@@ -80,7 +94,11 @@ pub(in crate::cli) struct EnvArgs {
     format: OptFormat,
     output_buffer_size: OptBufOut,
     input_buffer_size: OptBufIn,
+    input_buffer_max: OptBufInMax,
+    sort_buffer_size: OptBufSort,
     linereader_eol: OptEOL,
+    batch_size: OptBatchSize,
+    channel_depth: OptChanDepth,
     factor: OptFactor,
 }
 
@@ -124,20 +142,30 @@ impl EnvArgs {
                     log_err(s.parse::<char>(), &s).map(|c| Guard::new(Some(c)))
                 }
             }),
-            format: vars.get(&Kind::Format).map(|s| {
-                s.split('.')
-                    .map(|field| Field::try_from_whitelist(field, &VALID_FIELDS))
-                    .collect()
-            }),
+            format: vars
+                .get(&Kind::Format)
+                .map(|s| parse_format(s, &VALID_FIELDS)),
             output_buffer_size: vars
                 .get(&Kind::BufOut)
                 .and_then(|s| log_err(s.parse::<usize>(), &s)),
             input_buffer_size: vars
                 .get(&Kind::BufIn)
                 .and_then(|s| log_err(s.parse::<usize>(), &s)),
+            input_buffer_max: vars
+                .get(&Kind::BufInMax)
+                .and_then(|s| log_err(s.parse::<usize>(), &s)),
+            sort_buffer_size: vars
+                .get(&Kind::BufSort)
+                .and_then(|s| log_err(s.parse::<usize>(), &s)),
             linereader_eol: vars
                 .get(&Kind::RdrEol)
-                .and_then(|s| log_err(s.parse::<char>(), &s)),
+                .and_then(|s| log_err(RecordSeparator::try_from(s.as_str()), &s)),
+            batch_size: vars
+                .get(&Kind::BatchSize)
+                .and_then(|s| log_err(s.parse::<usize>(), &s)),
+            channel_depth: vars
+                .get(&Kind::ChanDepth)
+                .and_then(|s| log_err(s.parse::<usize>(), &s)),
             factor: vars
                 .get(&Kind::Factor)
                 .and_then(|s| log_err(parse_factor(&s), &s)),
@@ -156,7 +184,11 @@ impl ConfigMerge for EnvArgs {
         EnvArgs::priority_merge(&mut self.format, other.format());
         EnvArgs::priority_merge(&mut self.output_buffer_size, other.output_buffer_size());
         EnvArgs::priority_merge(&mut self.input_buffer_size, other.input_buffer_size());
+        EnvArgs::priority_merge(&mut self.input_buffer_max, other.input_buffer_max());
+        EnvArgs::priority_merge(&mut self.sort_buffer_size, other.sort_buffer_size());
         EnvArgs::priority_merge(&mut self.linereader_eol, other.linereader_eol());
+        EnvArgs::priority_merge(&mut self.batch_size, other.batch_size());
+        EnvArgs::priority_merge(&mut self.channel_depth, other.channel_depth());
         EnvArgs::priority_merge(&mut self.factor, other.factor());
     }
 
@@ -184,7 +216,7 @@ impl ConfigMerge for EnvArgs {
         self.guard.take()
     }
 
-    fn format(&mut self) -> Option<Result<Vec<Field>>> {
+    fn format(&mut self) -> Option<Result<Vec<Column>>> {
         self.format.take()
     }
 
@@ -192,14 +224,30 @@ impl ConfigMerge for EnvArgs {
         self.input_buffer_size.take()
     }
 
+    fn input_buffer_max(&mut self) -> Option<usize> {
+        self.input_buffer_max.take()
+    }
+
+    fn sort_buffer_size(&mut self) -> Option<usize> {
+        self.sort_buffer_size.take()
+    }
+
     fn output_buffer_size(&mut self) -> Option<usize> {
         self.output_buffer_size.take()
     }
 
-    fn linereader_eol(&mut self) -> Option<char> {
+    fn linereader_eol(&mut self) -> Option<RecordSeparator> {
         self.linereader_eol.take()
     }
 
+    fn batch_size(&mut self) -> Option<usize> {
+        self.batch_size.take()
+    }
+
+    fn channel_depth(&mut self) -> Option<usize> {
+        self.channel_depth.take()
+    }
+
     fn factor(&mut self) -> Option<usize> {
         self.factor.take()
     }
@@ -301,6 +349,7 @@ mod tests {
         super::*,
         crate::models::{
             block::{Delimiter, Guard},
+            column::Column,
             field::Field,
         },
     };
@@ -415,7 +464,13 @@ mod tests {
 
         assert_eq!(
             format.as_ref().map(|v| v.as_slice()),
-            Some([Field::Identifier, Field::Pointer, Field::Type, Field::Value].as_ref())
+            Some(
+                [Field::Identifier, Field::Pointer, Field::Type, Field::Value]
+                    .iter()
+                    .map(|f| Column::from(*f))
+                    .collect::<Vec<_>>()
+                    .as_slice()
+            )
         );
 
         Ok(())
@@ -428,7 +483,7 @@ mod tests {
 
         assert_eq!(
             format.as_ref().map(|v| v.as_slice()),
-            Some([Field::Identifier].as_ref())
+            Some([Column::from(Field::Identifier)].as_ref())
         );
 
         Ok(())
@@ -441,6 +496,34 @@ mod tests {
         assert_eq!(data.input_buffer_size(), Some(16))
     }
 
+    #[test]
+    fn arg_batch_size() {
+        let mut data = mock!(Kind::BatchSize, "64");
+
+        assert_eq!(data.batch_size(), Some(64))
+    }
+
+    #[test]
+    fn arg_channel_depth() {
+        let mut data = mock!(Kind::ChanDepth, "4");
+
+        assert_eq!(data.channel_depth(), Some(4))
+    }
+
+    #[test]
+    fn arg_buf_in_max() {
+        let mut data = mock!(Kind::BufInMax, "1024");
+
+        assert_eq!(data.input_buffer_max(), Some(1024))
+    }
+
+    #[test]
+    fn arg_buf_sort() {
+        let mut data = mock!(Kind::BufSort, "512");
+
+        assert_eq!(data.sort_buffer_size(), Some(512))
+    }
+
     #[test]
     fn arg_buf_out() {
         let mut data = mock!(Kind::BufOut, "64");
@@ -452,7 +535,17 @@ mod tests {
     fn arg_linereader_eol() {
         let mut data = mock!(Kind::RdrEol, ".");
 
-        assert_eq!(data.linereader_eol(), Some('.'))
+        assert_eq!(data.linereader_eol(), RecordSeparator::try_from(".").ok())
+    }
+
+    #[test]
+    fn arg_linereader_eol_multi_byte() {
+        let mut data = mock!(Kind::RdrEol, "\\x1e\\n");
+
+        assert_eq!(
+            data.linereader_eol().as_ref().map(|sep| sep.as_bytes()),
+            Some(&b"\x1e\n"[..])
+        )
     }
 
     #[test]
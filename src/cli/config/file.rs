@@ -4,8 +4,9 @@ use {
         cli::VALID_FIELDS,
         models::{
             block::{Delimiter, Guard},
+            column::{parse_format, Column},
             error::Result as CrateResult,
-            field::Field,
+            record::RecordSeparator,
         },
         with_log,
     },
@@ -76,7 +77,11 @@ pub(in crate::cli) struct FileArgs {
     format: OptFormat,
     output_buffer_size: OptBufOut,
     input_buffer_size: OptBufIn,
+    input_buffer_max: OptBufInMax,
+    sort_buffer_size: OptBufSort,
     linereader_eol: OptEOL,
+    batch_size: OptBatchSize,
+    channel_depth: OptChanDepth,
     factor: OptFactor,
 }
 
@@ -91,7 +96,11 @@ impl ConfigMerge for FileArgs {
         FileArgs::priority_merge(&mut self.format, other.format());
         FileArgs::priority_merge(&mut self.output_buffer_size, other.output_buffer_size());
         FileArgs::priority_merge(&mut self.input_buffer_size, other.input_buffer_size());
+        FileArgs::priority_merge(&mut self.input_buffer_max, other.input_buffer_max());
+        FileArgs::priority_merge(&mut self.sort_buffer_size, other.sort_buffer_size());
         FileArgs::priority_merge(&mut self.linereader_eol, other.linereader_eol());
+        FileArgs::priority_merge(&mut self.batch_size, other.batch_size());
+        FileArgs::priority_merge(&mut self.channel_depth, other.channel_depth());
         FileArgs::priority_merge(&mut self.factor, other.factor());
     }
 
@@ -119,7 +128,7 @@ impl ConfigMerge for FileArgs {
         self.guard.take()
     }
 
-    fn format(&mut self) -> Option<CrateResult<Vec<Field>>> {
+    fn format(&mut self) -> Option<CrateResult<Vec<Column>>> {
         self.format.take()
     }
 
@@ -127,14 +136,30 @@ impl ConfigMerge for FileArgs {
         self.input_buffer_size.take()
     }
 
+    fn input_buffer_max(&mut self) -> Option<usize> {
+        self.input_buffer_max.take()
+    }
+
+    fn sort_buffer_size(&mut self) -> Option<usize> {
+        self.sort_buffer_size.take()
+    }
+
     fn output_buffer_size(&mut self) -> Option<usize> {
         self.output_buffer_size.take()
     }
 
-    fn linereader_eol(&mut self) -> Option<char> {
+    fn linereader_eol(&mut self) -> Option<RecordSeparator> {
         self.linereader_eol.take()
     }
 
+    fn batch_size(&mut self) -> Option<usize> {
+        self.batch_size.take()
+    }
+
+    fn channel_depth(&mut self) -> Option<usize> {
+        self.channel_depth.take()
+    }
+
     fn factor(&mut self) -> Option<usize> {
         self.factor.take()
     }
@@ -174,7 +199,11 @@ impl From<ArgsBuilder> for FileArgs {
                     SubConfigBuilder {
                         output_buffer_size,
                         input_buffer_size,
+                        input_buffer_max,
+                        sort_buffer_size,
                         linereader_eol,
+                        batch_size,
+                        channel_depth,
                         factor,
                     } => Self {
                         debug,
@@ -186,7 +215,11 @@ impl From<ArgsBuilder> for FileArgs {
                         format,
                         output_buffer_size,
                         input_buffer_size,
+                        input_buffer_max,
+                        sort_buffer_size,
                         linereader_eol,
+                        batch_size,
+                        channel_depth,
                         factor,
                     },
                 },
@@ -200,7 +233,11 @@ impl From<ArgsBuilder> for FileArgs {
                     format,
                     output_buffer_size: None,
                     input_buffer_size: None,
+                    input_buffer_max: None,
+                    sort_buffer_size: None,
                     linereader_eol: None,
+                    batch_size: None,
+                    channel_depth: None,
                     factor: None,
                 },
             },
@@ -221,7 +258,7 @@ struct ArgsBuilder {
     delimiter: Option<Delimiter>,
     guard: Option<Guard>,
     #[serde(deserialize_with = "deserialize_format", default)]
-    format: Option<CrateResult<Vec<Field>>>,
+    format: Option<CrateResult<Vec<Column>>>,
     #[serde(rename = "config")]
     subconfig: Option<SubConfigBuilder>,
 }
@@ -232,23 +269,31 @@ struct SubConfigBuilder {
     output_buffer_size: Option<usize>,
     #[serde(rename = "buf_in", alias = "buf-in")]
     input_buffer_size: Option<usize>,
-    linereader_eol: Option<char>,
+    #[serde(rename = "buf_in_max", alias = "buf-in-max")]
+    input_buffer_max: Option<usize>,
+    #[serde(rename = "buf_sort", alias = "buf-sort")]
+    sort_buffer_size: Option<usize>,
+    linereader_eol: Option<RecordSeparator>,
+    #[serde(alias = "batch-size")]
+    batch_size: Option<usize>,
+    #[serde(rename = "chan_depth", alias = "chan-depth")]
+    channel_depth: Option<usize>,
     #[serde(deserialize_with = "deserialize_factor", default)]
     factor: Option<usize>,
 }
 
 /// Custom deserializer for the string describing the output format
-fn deserialize_format<'de, D>(deserializer: D) -> Result<Option<CrateResult<Vec<Field>>>, D::Error>
+fn deserialize_format<'de, D>(deserializer: D) -> Result<Option<CrateResult<Vec<Column>>>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    struct FormatVisitor(PhantomData<fn() -> Option<CrateResult<Vec<Field>>>>);
+    struct FormatVisitor(PhantomData<fn() -> Option<CrateResult<Vec<Column>>>>);
 
     impl<'de> Visitor<'de> for FormatVisitor {
-        type Value = Option<CrateResult<Vec<Field>>>;
+        type Value = Option<CrateResult<Vec<Column>>>;
 
         fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("a dot separated list of fields")
+            formatter.write_str("a dot separated list of columns")
         }
 
         fn visit_none<E: deError>(self) -> Result<Self::Value, E> {
@@ -256,11 +301,7 @@ where
         }
 
         fn visit_str<E: deError>(self, v: &str) -> Result<Self::Value, E> {
-            Ok(Some(
-                v.split('.')
-                    .map(|field| Field::try_from_whitelist(field, &VALID_FIELDS))
-                    .collect(),
-            ))
+            Ok(Some(parse_format(v, &VALID_FIELDS)))
         }
     }
 
@@ -358,7 +399,11 @@ pub(super) use toml::from_str as deserialize_str;
 
 #[cfg(test)]
 mod tests {
-    use {super::*, crate::cli::ConfigMerge, std::error};
+    use {
+        super::*,
+        crate::{cli::ConfigMerge, models::field::Field},
+        std::{convert::TryFrom, error},
+    };
 
     type Result<T> = std::result::Result<T, Box<dyn error::Error>>;
 
@@ -518,7 +563,13 @@ mod tests {
 
         assert_eq!(
             format.as_ref().map(|v| v.as_slice()),
-            Some([Field::Identifier, Field::Pointer, Field::Type, Field::Value].as_ref())
+            Some(
+                [Field::Identifier, Field::Pointer, Field::Type, Field::Value]
+                    .iter()
+                    .map(|f| Column::from(*f))
+                    .collect::<Vec<_>>()
+                    .as_slice()
+            )
         );
 
         Ok(())
@@ -531,7 +582,7 @@ mod tests {
 
         assert_eq!(
             format.as_ref().map(|v| v.as_slice()),
-            Some([Field::Identifier].as_ref())
+            Some([Column::from(Field::Identifier)].as_ref())
         );
 
         Ok(())
@@ -545,6 +596,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn arg_batch_size() -> Result<()> {
+        let mut data = mock!("batch-size = 64" => "config")?;
+
+        assert_eq!(data.batch_size(), Some(64));
+        Ok(())
+    }
+
+    #[test]
+    fn arg_channel_depth() -> Result<()> {
+        let mut data = mock!("chan_depth = 4" => "config")?;
+
+        assert_eq!(data.channel_depth(), Some(4));
+        Ok(())
+    }
+
+    #[test]
+    fn arg_input_buffer_max() -> Result<()> {
+        let mut data = mock!("buf-in-max = 1024" => "config")?;
+
+        assert_eq!(data.input_buffer_max(), Some(1024));
+        Ok(())
+    }
+
+    #[test]
+    fn arg_sort_buffer_size() -> Result<()> {
+        let mut data = mock!("buf-sort = 512" => "config")?;
+
+        assert_eq!(data.sort_buffer_size(), Some(512));
+        Ok(())
+    }
+
     #[test]
     fn arg_output_buffer_size() -> Result<()> {
         let mut data = mock!("buf_out = 64" => "config")?;
@@ -557,7 +640,23 @@ mod tests {
     fn arg_linereader_eol() -> Result<()> {
         let mut data = mock!("linereader_eol = ';'" => "config")?;
 
-        assert_eq!(data.linereader_eol(), Some(';'));
+        assert_eq!(data.linereader_eol(), RecordSeparator::try_from(";").ok());
+        Ok(())
+    }
+
+    #[test]
+    fn arg_linereader_eol_multi_byte() -> Result<()> {
+        let mut literal = mock!("linereader_eol = '\\r\\n'" => "config")?;
+        let mut basic = mock!("linereader_eol = \"\\r\\n\"" => "config")?;
+
+        assert_eq!(
+            literal.linereader_eol().as_ref().map(|sep| sep.as_bytes()),
+            Some(&b"\r\n"[..])
+        );
+        assert_eq!(
+            basic.linereader_eol().as_ref().map(|sep| sep.as_bytes()),
+            Some(&b"\r\n"[..])
+        );
         Ok(())
     }
 
@@ -42,14 +42,30 @@ pub(in crate::cli) trait ConfigMerge {
         None
     }
 
+    fn input_buffer_max(&mut self) -> OptBufInMax {
+        None
+    }
+
     fn output_buffer_size(&mut self) -> OptBufOut {
         None
     }
 
+    fn sort_buffer_size(&mut self) -> OptBufSort {
+        None
+    }
+
     fn linereader_eol(&mut self) -> OptEOL {
         None
     }
 
+    fn batch_size(&mut self) -> OptBatchSize {
+        None
+    }
+
+    fn channel_depth(&mut self) -> OptChanDepth {
+        None
+    }
+
     fn factor(&mut self) -> OptFactor {
         None
     }
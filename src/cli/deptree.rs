@@ -1,15 +1,36 @@
 use {crate::models::field::Field, std::collections::HashMap};
 
-const FIELDS: [Field; 7] = [
+const FIELDS: [Field; 23] = [
     Field::Delimiter,
+    Field::Depth,
     Field::Guard,
+    Field::Hash,
     Field::Identifier,
+    Field::Index,
     Field::JmesPath,
+    Field::JqPath,
+    Field::JsonPath,
+    Field::Key,
+    Field::Len,
+    Field::Length,
+    Field::Line,
+    Field::Offset,
+    Field::Parent,
     Field::Pointer,
+    Field::Raw,
+    Field::Row,
+    Field::SemType,
+    Field::Size,
+    Field::Source,
     Field::Type,
     Field::Value,
 ];
 
+/// A dependency map flattened into a table indexed by Field, as it is
+/// checked for every output row. None means the Field isn't needed,
+/// otherwise the bool is true if it is stored
+pub(in crate::cli) type DependencyTable = [Option<bool>; FIELDS.len()];
+
 /// There are two variants of dependency that need to be mapped:
 /// 1. Needs to be calculated
 /// 2. Needs to be stored (which implies 1)
@@ -29,10 +50,26 @@ impl DependencyTree {
             .iter()
             .map(|field| match field {
                 f @ Field::Delimiter => (f, None),
+                f @ Field::Depth => (f, Some(vec![Field::Pointer])),
                 f @ Field::Guard => (f, None),
+                f @ Field::Hash => (f, Some(vec![Field::Value])),
                 f @ Field::Identifier => (f, Some(vec![Field::Guard, Field::Delimiter])),
+                f @ Field::Index => (f, Some(vec![Field::Pointer])),
                 f @ Field::JmesPath => (f, Some(vec![Field::Pointer])),
+                f @ Field::JqPath => (f, Some(vec![Field::Pointer])),
+                f @ Field::JsonPath => (f, Some(vec![Field::Pointer])),
+                f @ Field::Key => (f, Some(vec![Field::Pointer])),
+                f @ Field::Len => (f, Some(vec![Field::Value])),
+                f @ Field::Length => (f, Some(vec![Field::Guard, Field::Delimiter])),
+                f @ Field::Line => (f, Some(vec![Field::Guard, Field::Delimiter])),
+                f @ Field::Offset => (f, Some(vec![Field::Guard, Field::Delimiter])),
+                f @ Field::Parent => (f, Some(vec![Field::Pointer])),
                 f @ Field::Pointer => (f, Some(vec![Field::Value])),
+                f @ Field::Raw => (f, Some(vec![Field::Guard, Field::Delimiter])),
+                f @ Field::Row => (f, Some(vec![Field::Guard, Field::Delimiter])),
+                f @ Field::SemType => (f, Some(vec![Field::Value])),
+                f @ Field::Size => (f, Some(vec![Field::Value])),
+                f @ Field::Source => (f, Some(vec![Field::Guard, Field::Delimiter])),
                 f @ Field::Type => (f, Some(vec![Field::Value])),
                 f @ Field::Value => (f, Some(vec![Field::Guard, Field::Delimiter])),
             })
@@ -47,21 +84,19 @@ impl DependencyTree {
         relevant: F,
     ) -> HashMap<Field, bool> {
         let mut set = HashMap::<Field, bool>::with_capacity(FIELDS.len());
-        // Populate the dependency map with with all secondary Fields
-        relevant
-            .as_ref()
-            .iter()
-            .scan(Vec::<(Field, bool)>::new(), |buffer, field| {
-                match self.map.get(field).unwrap() {
-                    Some(deps) => buffer.extend(deps.iter().map(|f| (*f, false))),
-                    None => {}
+        // Populate the dependency map with with all secondary Fields,
+        // following each dependency down to its own dependencies
+        let mut stack = relevant.as_ref().to_vec();
+        while let Some(field) = stack.pop() {
+            if let Some(deps) = self.map.get(&field).unwrap() {
+                for dep in deps {
+                    if !set.contains_key(dep) {
+                        set.insert(*dep, false);
+                        stack.push(*dep);
+                    }
                 }
-
-                buffer.pop()
-            })
-            .for_each(|(field, is_output)| {
-                set.entry(field).or_insert(is_output);
-            });
+            }
+        }
         // Populate the dependency map with the primary Fields, overwriting secondary values
         relevant
             .as_ref()
@@ -73,4 +108,14 @@ impl DependencyTree {
 
         set
     }
+
+    /// Generate a dependency map, as a table
+    pub(in crate::cli) fn generate_table<F: AsRef<[Field]>>(&self, relevant: F) -> DependencyTable {
+        let mut table = [None; FIELDS.len()];
+        for (field, is_output) in self.generate_list(relevant) {
+            table[field as usize] = Some(is_output)
+        }
+
+        table
+    }
 }
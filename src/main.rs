@@ -50,7 +50,7 @@ fn try_main() -> Result<()> {
     // Hot loop
     for source in CLI.reader_list() {
         let read_from: ReadKind = set_reader(source);
-        tx.send(read_from).context(Context::umcc("Reader"))?;
+        tx.send(read_from).context(Context::umcc("Dispatcher"))?;
     }
 
     // Signals that that no new input sources will be sent
@@ -60,7 +60,7 @@ fn try_main() -> Result<()> {
     reader
         .join()
         .map_err(|_| ErrorKind::ThreadFailed)
-        .context(Context::tp("Reader"))??;
+        .context(Context::tp("Dispatcher"))??;
     // Return 0
     Ok(())
 }
@@ -2,13 +2,19 @@ use {
     crate::{
         cli::ProgramArgs,
         models::{
-            block::{Identifier, JType, JmesPath, JsonPointer, JsonValue},
+            block::{
+                ArrayIndex, ByteLength, ByteOffset, ContainerSize, ContentHash, Identifier, JType,
+                JmesPath, JqPath, JsonPath, JsonPointer, JsonValue, ParentPointer, PointerDepth,
+                PointerKey, RawJson, SemanticType, StringLength,
+            },
             builder::OutputBuilder,
-            error::{Context, ErrContext, Error},
+            error::Error,
             field::Field,
-            pointer::{Pointer, PointerKind, PointerParts},
+            origin::{Origin, Span},
+            pointer::{Pointer, PointerKind},
+            subtree::{Subtree, Subtrees},
+            ToBuilder,
         },
-        CLI,
     },
     serde_json::{
         from_slice, Value as Json,
@@ -25,6 +31,7 @@ use {
         io::{Read as ioRead, Stdin},
         path::PathBuf,
         str::{from_utf8, FromStr},
+        sync::Arc,
     },
 };
 
@@ -63,17 +70,25 @@ impl std::fmt::Display for ReadFrom {
 /// avoiding dynamic dispatch
 #[derive(Debug)]
 pub enum ReadKind {
-    File(File),
+    File(File, PathBuf),
     Stdin(Stdin),
 }
 
 impl ReadKind {
     pub fn into_inner(self) -> Box<dyn ioRead> {
         match self {
-            ReadKind::File(f) => Box::new(f),
+            ReadKind::File(f, _) => Box::new(f),
             ReadKind::Stdin(s) => Box::new(s),
         }
     }
+
+    /// The path the input was opened from, or '-' for stdin
+    pub fn source(&self) -> Arc<str> {
+        match self {
+            ReadKind::File(_, path) => Arc::from(path.to_string_lossy()),
+            ReadKind::Stdin(_) => Arc::from("-"),
+        }
+    }
 }
 
 /// Contains the regex and the field
@@ -105,56 +120,32 @@ impl RegexOptions {
             _ => false,
         }
     }
-}
-
-/// Effectively implements a specialized Display for Pointer
-struct JmesDisplay<'a>(&'a Vec<PointerParts>);
-
-impl<'a> From<&'a Vec<PointerParts>> for JmesDisplay<'a> {
-    fn from(p: &'a Vec<PointerParts>) -> JmesDisplay<'a> {
-        JmesDisplay(p)
-    }
-}
 
-impl<'a> std::fmt::Display for JmesDisplay<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        // Check for unsupported JSON, i.e JSON literals
-        if self.0.is_empty() {
-            for (first, _, item) in self
-                .0
-                .iter()
-                .filter(|part| match part {
-                    PointerParts::Slash => false,
-                    _ => true,
-                })
-                .identify_first_last()
-            {
-                match item {
-                    PointerParts::Object(s) => {
-                        if first {
-                            write!(f, "{}", s)?
-                        } else {
-                            write!(f, ".{}", s)?
-                        }
-                    }
-                    PointerParts::Array(u) => write!(f, "[{}]", u)?,
-                    PointerParts::Slash => {}
-                }
-            }
-
-            Ok(())
-        } else {
-            // If unsupported, return what appears to be the standard response
-            write!(f, "null")
+    pub fn is_row(&self) -> bool {
+        match self.field {
+            Field::Row => true,
+            _ => false,
         }
     }
 }
 
+/// A JSON value waiting to be unwound, with its pointer, input span
+/// and the subtrees of its collapsed children (only the root has any)
+type Pending<'j> = (
+    Option<&'j Json>,
+    Option<PointerKind>,
+    Option<&'j Span>,
+    Option<&'j Subtrees>,
+);
+
 /// Struct responsible for turning each unwound
 /// JSON object into the components that Output / Builder
 /// will use
 pub struct BlockGenerator<'j, 'args: 'j> {
-    queue: VecDeque<(Option<&'j Json>, Option<PointerKind>)>,
+    queue: VecDeque<Pending<'j>>,
+    /// Used instead of the queue when output follows the source order,
+    /// the flag marks object members, which get a row of their own
+    stack: Vec<(Pending<'j>, bool)>,
     buffer: Vec<OutputBuilder>,
     opts: &'args ProgramArgs,
     pristine: bool,
@@ -164,16 +155,27 @@ impl<'j, 'args> BlockGenerator<'j, 'args> {
     pub fn new(
         opts: &'args ProgramArgs,
         json: Option<&'j Json>,
+        span: Option<&'j Span>,
+        children: Option<&'j Subtrees>,
         meta: (Option<PointerKind>, Option<usize>),
     ) -> Self {
-        let (mut queue, buffer) = match (json, meta.1) {
-            (_, Some(hint)) => (VecDeque::with_capacity(hint), Vec::with_capacity(hint)),
-            (_, None) => (VecDeque::new(), Vec::new()),
+        let (mut queue, mut stack, buffer) = match (opts.source_order(), meta.1) {
+            (false, Some(hint)) => (
+                VecDeque::with_capacity(hint),
+                Vec::new(),
+                Vec::with_capacity(hint),
+            ),
+            (true, Some(hint)) => (VecDeque::new(), Vec::with_capacity(hint), Vec::new()),
+            (_, None) => (VecDeque::new(), Vec::new(), Vec::new()),
         };
-        queue.push_back((json, meta.0));
+        match opts.source_order() {
+            true => stack.push(((json, meta.0, span, children), false)),
+            false => queue.push_back((json, meta.0, span, children)),
+        }
 
         BlockGenerator {
             queue,
+            stack,
             buffer,
             opts,
             pristine: true,
@@ -181,72 +183,82 @@ impl<'j, 'args> BlockGenerator<'j, 'args> {
     }
 
     pub fn parse_next(&mut self) -> Option<OutputBuilder> {
+        if self.opts.source_order() {
+            return self.parse_ordered();
+        }
         while let Some(value) = self.queue.pop_front() {
             match value {
-                (Some(jObject(map)), ref ptr) => {
+                (Some(jObject(map)), ref ptr, span, children) => {
                     for (k, v) in map.iter() {
                         let new_path = ptr.as_ref().map(|p| p.clone_extend(k.as_str()));
-                        if v.is_object() {
-                            self.output_checked(&new_path, None.into(), v.into());
+                        let new_span = span.and_then(|s| s.member(k));
+                        if v.is_object() || v.is_array() {
+                            let subtree = children.and_then(|c| c.member(k));
+                            self.output_container(&new_path, new_span, subtree, v);
                         }
-                        if v.is_array() {
-                            self.output_checked(&new_path, None.into(), v.into());
-                        }
-                        self.queue.push_back((Some(v), new_path));
+                        self.queue.push_back((Some(v), new_path, new_span, None));
                     }
                 }
-                (Some(jArray(a)), ref ptr) => {
+                (Some(jArray(a)), ref ptr, span, _) => {
                     for (i, v) in a.iter().map(Some).enumerate() {
                         let new_path = ptr.as_ref().map(|p| p.clone_extend(i));
-                        self.queue.push_back((v, new_path));
+                        let new_span = span.and_then(|s| s.element(i));
+                        self.queue.push_back((v, new_path, new_span, None));
                     }
                 }
-                (Some(jString(val)), ref ptr) => {
-                    self.output_checked(
-                        &ptr,
-                        Some(val)
-                            .filter(|_| self.opts.should_calculate(Field::Value))
-                            .cloned()
-                            .into(),
-                        value.0.unwrap().into(),
-                    );
+                (Some(json), ref ptr, span, _) => {
+                    self.output_scalar(ptr, span, json);
                     break;
                 }
-                (Some(jNumber(val)), ref ptr) => {
-                    self.output_checked(
-                        &ptr,
-                        Some(val)
-                            .filter(|_| self.opts.should_calculate(Field::Value))
-                            .map(|val| val.to_string())
-                            .into(),
-                        value.0.unwrap().into(),
-                    );
-                    break;
+                (None, _, _, _) => {
+                    // Ugly fix for if there is no Json to unwind, should rewrite this somehow
+                    if self.pristine {
+                        self.pristine = false;
+                        return Some(OutputBuilder::new());
+                    } else {
+                        break;
+                    }
                 }
-                (Some(jBool(val)), ref ptr) => {
-                    self.output_checked(
-                        &ptr,
-                        Some(val)
-                            .filter(|_| self.opts.should_calculate(Field::Value))
-                            .map(|val| val.to_string())
-                            .into(),
-                        value.0.unwrap().into(),
-                    );
-                    break;
+            }
+        }
+        self.buffer.pop()
+    }
+
+    /// Walks the JSON depth first, yielding rows in the order they appear
+    /// in the source, with each container before its children.
+    /// Docs are built whole in this order, so never have subtrees
+    fn parse_ordered(&mut self) -> Option<OutputBuilder> {
+        while let Some(((value, ptr, span, _), member)) = self.stack.pop() {
+            match value {
+                Some(json @ jObject(map)) => {
+                    // Reversed, so the first key is the first popped
+                    for (k, v) in map.iter().rev() {
+                        let new_path = ptr.as_ref().map(|p| p.clone_extend(k.as_str()));
+                        let new_span = span.and_then(|s| s.member(k));
+                        self.stack.push(((Some(v), new_path, new_span, None), true));
+                    }
+                    if member {
+                        self.output_container(&ptr, span, None, json);
+                        break;
+                    }
+                }
+                Some(json @ jArray(a)) => {
+                    for (i, v) in a.iter().enumerate().rev() {
+                        let new_path = ptr.as_ref().map(|p| p.clone_extend(i));
+                        let new_span = span.and_then(|s| s.element(i));
+                        self.stack
+                            .push(((Some(v), new_path, new_span, None), false));
+                    }
+                    if member {
+                        self.output_container(&ptr, span, None, json);
+                        break;
+                    }
                 }
-                (Some(tp @ jNull), ptr) => {
-                    self.output_checked(
-                        &ptr,
-                        Some(tp)
-                            .filter(|_| self.opts.should_calculate(Field::Value))
-                            .map(|_| String::from("null"))
-                            .into(),
-                        tp.into(),
-                    );
+                Some(json) => {
+                    self.output_scalar(&ptr, span, json);
                     break;
                 }
-                (None, _) => {
-                    // Ugly fix for if there is no Json to unwind, should rewrite this somehow
+                None => {
                     if self.pristine {
                         self.pristine = false;
                         return Some(OutputBuilder::new());
@@ -259,26 +271,117 @@ impl<'j, 'args> BlockGenerator<'j, 'args> {
         self.buffer.pop()
     }
 
+    /// Stores a row for any JSON that isn't an object or array
+    fn output_scalar(&mut self, ptr: &Option<PointerKind>, span: Option<&Span>, json: &Json) {
+        let value = match json {
+            _ if !self.opts.should_calculate(Field::Value) => None,
+            jString(val) => Some(val.clone()),
+            jNumber(val) => Some(val.to_string()),
+            jBool(val) => Some(val.to_string()),
+            jNull => Some(String::from("null")),
+            jObject(_) | jArray(_) => unreachable!("Containers are never output as scalars"),
+        };
+        self.output_checked(ptr, span, None, value.into(), json);
+    }
+
+    /// Stores a row for an object or array, its value being
+    /// its size if requested, otherwise empty. The subtree
+    /// describes it if it was collapsed, leaving json empty
+    fn output_container(
+        &mut self,
+        ptr: &Option<PointerKind>,
+        span: Option<&Span>,
+        subtree: Option<&Subtree>,
+        json: &Json,
+    ) {
+        let size = subtree
+            .map(Subtree::size)
+            .or_else(|| ContainerSize::of(json));
+        let value = match size {
+            _ if !self.opts.should_calculate(Field::Value) => None,
+            Some(size) if self.opts.value_size() => Some(size.to_string()),
+            _ => None,
+        };
+        self.output_checked(ptr, span, subtree, value.into(), json);
+    }
+
     /// Custom output storage checker due to the difficulties induced by PointerKind
-    fn output_checked(&mut self, ptr: &Option<PointerKind>, jval: JsonValue, jtype: JType) {
+    fn output_checked(
+        &mut self,
+        ptr: &Option<PointerKind>,
+        span: Option<&Span>,
+        subtree: Option<&Subtree>,
+        jval: JsonValue,
+        json: &Json,
+    ) {
         let s = &*self;
         let mut builder = OutputBuilder::new();
 
         if s.opts.should_store(Field::Pointer) {
-            builder.store_unchecked(
-                ptr.as_ref()
-                    .map(|p| -> JsonPointer { p.as_complete().into() }),
-            )
+            builder.store_unchecked(ptr.as_ref().map(|p| -> JsonPointer { p.clone().into() }))
         }
         if s.opts.should_store(Field::Value) {
             builder.store_unchecked(Some(jval))
         }
         if s.opts.should_store(Field::Type) {
-            builder.store_unchecked(Some(jtype))
+            builder.store_unchecked(Some(match s.opts.number_types() {
+                true => JType::refined(json),
+                false => JType::from(json),
+            }))
         }
         if s.opts.should_store(Field::JmesPath) {
             builder.store_unchecked(ptr.as_ref().map(|p| JmesPath::from(p)))
         }
+        if s.opts.should_store(Field::JsonPath) {
+            builder.store_unchecked(ptr.as_ref().map(JsonPath::from))
+        }
+        if s.opts.should_store(Field::JqPath) {
+            builder.store_unchecked(ptr.as_ref().map(JqPath::from))
+        }
+        if s.opts.should_store(Field::Depth) {
+            builder.store_unchecked(ptr.as_ref().map(PointerDepth::from))
+        }
+        if s.opts.should_store(Field::Key) {
+            builder.store_unchecked(ptr.as_ref().map(PointerKey::from))
+        }
+        if s.opts.should_store(Field::Parent) {
+            builder.store_unchecked(ptr.as_ref().map(ParentPointer::from))
+        }
+        if s.opts.should_store(Field::Index) {
+            builder.store_unchecked(ptr.as_ref().map(ArrayIndex::from))
+        }
+        if s.opts.should_store(Field::Offset) {
+            builder.store_unchecked(Some(ByteOffset::from(span)))
+        }
+        if s.opts.should_store(Field::Length) {
+            builder.store_unchecked(Some(ByteLength::from(span)))
+        }
+        if s.opts.should_store(Field::Raw) {
+            builder.store_unchecked(Some(match subtree.and_then(Subtree::raw) {
+                Some(raw) => RawJson::from(raw.to_string()),
+                None => RawJson::from(json),
+            }))
+        }
+        if s.opts.should_store(Field::Size) {
+            builder.store_unchecked(Some(match subtree {
+                Some(subtree) => ContainerSize::from(subtree),
+                None => ContainerSize::from(json),
+            }))
+        }
+        if s.opts.should_store(Field::Len) {
+            builder.store_unchecked(Some(StringLength::from(json)))
+        }
+        if s.opts.should_store(Field::Hash) {
+            builder.store_unchecked(Some(ContentHash::from(
+                match subtree.and_then(Subtree::hash) {
+                    Some(digest) => digest.to_string(),
+                    None => s.opts.hash_algorithm().digest(json),
+                },
+            )))
+        }
+        if s.opts.should_store(Field::SemType) {
+            builder.store_unchecked(Some(SemanticType::from(json)))
+        }
         self.buffer.push(builder);
     }
 }
@@ -295,6 +398,9 @@ type ToBlockGen = (
     Option<Json>,
     Option<Identifier>,
     (Option<PointerKind>, Option<usize>),
+    Option<Origin>,
+    Option<Span>,
+    Option<Subtrees>,
 );
 
 /// Convenience intermediate struct for
@@ -304,6 +410,9 @@ pub struct JsonPacket {
     ident: Option<Identifier>,
     base_path: Option<PointerKind>,
     json: Option<Json>,
+    origin: Option<Origin>,
+    span: Option<Span>,
+    subtrees: Option<Subtrees>,
 }
 
 impl JsonPacket {
@@ -320,35 +429,43 @@ impl JsonPacket {
 
     pub fn into_inner(self) -> ToBlockGen {
         let hint = self.size_hint();
-        (self.json, self.ident, (self.base_path, hint))
+        (
+            self.json,
+            self.ident,
+            (self.base_path, hint),
+            self.origin,
+            self.span,
+            self.subtrees,
+        )
     }
 }
 
-impl TryFrom<(Option<Identifier>, Option<PointerKind>, Option<Vec<u8>>)> for JsonPacket {
+impl TryFrom<ToBuilder> for JsonPacket {
     type Error = Error;
 
-    fn try_from(
-        packet: (Option<Identifier>, Option<PointerKind>, Option<Vec<u8>>),
-    ) -> std::result::Result<Self, Self::Error> {
+    fn try_from(packet: ToBuilder) -> std::result::Result<Self, Self::Error> {
         trace!(
             "trying to convert: {:?}",
             packet.2.as_ref().map(|vec| from_utf8(vec)).or_untracked()
         );
         let json: Option<Json> = packet
             .2
-            .map(|data| {
-                from_slice(data.as_slice()).context(
-                    Some(data.len())
-                        .filter(|l| *l >= CLI.input_buffer_size())
-                        .map(Context::DataLenEqualLineBufferLen),
-                )
-            })
+            .as_ref()
+            .map(|data| from_slice(data.as_slice()))
             .transpose()?;
+        // Only once the data is known to be valid JSON
+        let span = match (packet.3.as_ref(), packet.2.as_ref()) {
+            (Some(origin), Some(data)) => origin.index(data)?,
+            _ => None,
+        };
 
         Ok(JsonPacket {
             ident: packet.0,
             base_path: packet.1,
             json,
+            origin: packet.3,
+            span,
+            subtrees: packet.4,
         })
     }
 }
@@ -484,3 +601,78 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            cli::generate_cli,
+            models::{builder::Builder, write_formatted_output},
+        },
+        clap::AppSettings,
+        serde_json::from_str,
+    };
+
+    fn opts(args: &[&str]) -> ProgramArgs {
+        let store = generate_cli()
+            .setting(AppSettings::NoBinaryName)
+            .get_matches_from(args);
+
+        ProgramArgs::from_store(&store)
+    }
+
+    fn pointers(args: &[&str], json: &str) -> Vec<String> {
+        let opts = opts(args);
+        let json: Json = from_str(json).unwrap();
+
+        BlockGenerator::new(
+            &opts,
+            Some(&json),
+            None,
+            None,
+            (PointerKind::new(&opts), None),
+        )
+        .map(|output| {
+            Builder::<Field>::pointer(&output.done())
+                .unwrap()
+                .to_string()
+        })
+        .collect()
+    }
+
+    #[test]
+    #[cfg(not(feature = "preserve-order"))]
+    fn unwind_in_sorted_order() {
+        assert_eq!(pointers(&["-f", "jptr"], r#"{"b":1,"a":2}"#), ["/a", "/b"]);
+    }
+
+    #[test]
+    #[cfg(feature = "preserve-order")]
+    fn unwind_in_key_order() {
+        assert_eq!(pointers(&["-f", "jptr"], r#"{"b":1,"a":2}"#), ["/b", "/a"]);
+    }
+
+    #[test]
+    fn quoted_paths_written_as_csv() {
+        let opts = opts(&["-f", "jmes.jq"]);
+        let json = from_str(r#"{"a/b":{"x~y":[0,0,{"c d":1}]}}"#).unwrap();
+        let mut buffer = Vec::new();
+        let rows = BlockGenerator::new(
+            &opts,
+            Some(&json),
+            None,
+            None,
+            (PointerKind::new(&opts), None),
+        );
+        for mut output in rows {
+            output.store(&opts, Some(opts.delimiter()));
+            output.store(&opts, Some(opts.guard()));
+            write_formatted_output(&mut buffer, output.done(), opts.format()).unwrap();
+        }
+
+        let written = String::from_utf8_lossy(&buffer);
+        let expected = r#""""a/b"".""x~y""[2].""c d""",".[""a/b""][""x~y""][2][""c d""]""#;
+        assert!(written.lines().any(|line| line == expected), "{}", written);
+    }
+}
@@ -0,0 +1,127 @@
+use {
+    crate::models::error::{Context, ErrContext, Result},
+    std::{
+        mem::replace,
+        sync::mpsc::{Sender, SyncSender},
+    },
+};
+
+/// Any channel that can carry batches of T
+pub trait SendBatch<T> {
+    fn send_batch(&self, batch: Vec<T>) -> Result<()>;
+}
+
+impl<T> SendBatch<T> for SyncSender<Vec<T>> {
+    fn send_batch(&self, batch: Vec<T>) -> Result<()> {
+        self.send(batch).context(Context::udcc())?;
+
+        Ok(())
+    }
+}
+
+impl<T> SendBatch<T> for Sender<Vec<T>> {
+    fn send_batch(&self, batch: Vec<T>) -> Result<()> {
+        self.send(batch).context(Context::udcc())?;
+
+        Ok(())
+    }
+}
+
+/// Collects items into batches of up to 'size', only touching
+/// the underlying channel once a batch is full. Partial batches
+/// should be sent with flush, which reports any error. Anything
+/// left unflushed is sent when the sender is dropped
+pub struct BatchSender<T, S>
+where
+    S: SendBatch<T>,
+{
+    inner: S,
+    batch: Vec<T>,
+    size: usize,
+}
+
+impl<T, S> BatchSender<T, S>
+where
+    S: SendBatch<T>,
+{
+    pub fn new(inner: S, size: usize) -> Self {
+        BatchSender {
+            inner,
+            batch: Vec::with_capacity(size),
+            size,
+        }
+    }
+
+    pub fn send(&mut self, item: T) -> Result<()> {
+        self.batch.push(item);
+        if self.batch.len() >= self.size {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the current batch, if it has anything in it
+    pub fn flush(&mut self) -> Result<()> {
+        if !self.batch.is_empty() {
+            let batch = replace(&mut self.batch, Vec::with_capacity(self.size));
+            self.inner.send_batch(batch)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, S> Drop for BatchSender<T, S>
+where
+    S: SendBatch<T>,
+{
+    fn drop(&mut self) {
+        // Nothing to report to, the receiver is likely gone if this fails
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::sync::mpsc::channel};
+
+    #[test]
+    fn sends_full_batches() -> Result<()> {
+        let (tx, rx) = channel();
+        let mut batcher = BatchSender::new(tx, 2);
+        for i in 0..5 {
+            batcher.send(i)?;
+        }
+
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![vec![0, 1], vec![2, 3]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn flush_sends_partial_batch() -> Result<()> {
+        let (tx, rx) = channel();
+        let mut batcher = BatchSender::new(tx, 4);
+        batcher.flush()?;
+        batcher.send(0)?;
+        batcher.flush()?;
+
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![vec![0]]);
+        Ok(())
+    }
+
+    #[test]
+    fn drop_sends_partial_batch() -> Result<()> {
+        let (tx, rx) = channel();
+        let mut batcher = BatchSender::new(tx, 4);
+        batcher.send(0)?;
+        batcher.send(1)?;
+        drop(batcher);
+
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![vec![0, 1]]);
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 use {
     crate::models::{
         field::{AsField, Field},
-        pointer::{Pointer, PointerParts},
-        IdentifyFirstLast,
+        origin::{Origin, Span},
+        pointer::{PathSyntax, Pointer, PointerKind, PointerParts},
+        semtype::SemType,
+        subtree::Subtree,
     },
     serde_json::{
         Value as Json,
@@ -11,6 +13,7 @@ use {
             String as jString,
         },
     },
+    std::sync::Arc,
 };
 
 #[cfg(feature = "config-file")]
@@ -21,12 +24,28 @@ use serde::Deserialize;
 #[derive(Debug, Clone)]
 pub enum BlockKind {
     Ident(Identifier),
-    Delimiter(Delimiter),
+    Delimiter(Arc<Delimiter>),
     Guard(Guard),
     Type(JType),
     Pointer(JsonPointer),
     Value(JsonValue),
     Jmes(JmesPath),
+    JsonPath(JsonPath),
+    Jq(JqPath),
+    Depth(PointerDepth),
+    Key(PointerKey),
+    Parent(ParentPointer),
+    Index(ArrayIndex),
+    Source(SourcePath),
+    Line(LineNumber),
+    Offset(ByteOffset),
+    Length(ByteLength),
+    Row(RowNumber),
+    Raw(RawJson),
+    Size(ContainerSize),
+    Len(StringLength),
+    Hash(ContentHash),
+    SemType(SemanticType),
 }
 
 impl std::fmt::Display for BlockKind {
@@ -39,6 +58,22 @@ impl std::fmt::Display for BlockKind {
             BlockKind::Pointer(p) => write!(f, "{}", p),
             BlockKind::Value(v) => write!(f, "{}", v),
             BlockKind::Jmes(j) => write!(f, "{}", j),
+            BlockKind::JsonPath(j) => write!(f, "{}", j),
+            BlockKind::Jq(j) => write!(f, "{}", j),
+            BlockKind::Depth(p) => write!(f, "{}", p),
+            BlockKind::Key(p) => write!(f, "{}", p),
+            BlockKind::Parent(p) => write!(f, "{}", p),
+            BlockKind::Index(p) => write!(f, "{}", p),
+            BlockKind::Source(p) => write!(f, "{}", p),
+            BlockKind::Line(p) => write!(f, "{}", p),
+            BlockKind::Offset(p) => write!(f, "{}", p),
+            BlockKind::Length(p) => write!(f, "{}", p),
+            BlockKind::Row(p) => write!(f, "{}", p),
+            BlockKind::Raw(p) => write!(f, "{}", p),
+            BlockKind::Size(p) => write!(f, "{}", p),
+            BlockKind::Len(p) => write!(f, "{}", p),
+            BlockKind::Hash(p) => write!(f, "{}", p),
+            BlockKind::SemType(p) => write!(f, "{}", p),
         }
     }
 }
@@ -52,6 +87,35 @@ pub enum JType {
     Number,
     Bool,
     Null,
+    // Numbers told apart, see JType::refined
+    Integer,
+    Float,
+    BigInt,
+}
+
+impl JType {
+    /// As From<&Json>, but splitting numbers into integers, floats
+    /// and integers too large for 64 bits. The latter can only be
+    /// told apart with the exact-numbers feature, otherwise they are
+    /// parsed (losing precision) as floats
+    pub fn refined(json: &Json) -> Self {
+        match json {
+            jNumber(n) if n.is_i64() || n.is_u64() => JType::Integer,
+            jNumber(n) if cfg!(feature = "exact-numbers") && is_integer(&n.to_string()) => {
+                JType::BigInt
+            }
+            jNumber(_) => JType::Float,
+            json => JType::from(json),
+        }
+    }
+}
+
+/// Checks if a number is written without a fraction or exponent
+fn is_integer(number: &str) -> bool {
+    number
+        .trim_start_matches('-')
+        .bytes()
+        .all(|b| b.is_ascii_digit())
 }
 
 impl From<Json> for JType {
@@ -89,6 +153,9 @@ impl std::fmt::Display for JType {
             JType::Number => "Number",
             JType::Bool => "Bool",
             JType::Null => "Null",
+            JType::Integer => "Integer",
+            JType::Float => "Float",
+            JType::BigInt => "BigInt",
         };
 
         write!(f, "{}", kind)
@@ -149,20 +216,21 @@ impl std::fmt::Display for Identifier {
     }
 }
 
-/// Wrapper around the jptr type
+/// Wrapper around the jptr type, which is
+/// only assembled into a string when displayed
 #[derive(Debug, Clone)]
 pub struct JsonPointer {
-    inner: String,
+    inner: PointerKind,
 }
 
-impl From<String> for JsonPointer {
-    fn from(inner: String) -> Self {
+impl From<PointerKind> for JsonPointer {
+    fn from(inner: PointerKind) -> Self {
         JsonPointer { inner }
     }
 }
 
-impl AsRef<String> for JsonPointer {
-    fn as_ref(&self) -> &String {
+impl AsRef<PointerKind> for JsonPointer {
+    fn as_ref(&self) -> &PointerKind {
         &self.inner
     }
 }
@@ -275,7 +343,14 @@ impl std::fmt::Display for Delimiter {
 
 impl Into<BlockKind> for Delimiter {
     fn into(self) -> BlockKind {
-        BlockKind::Delimiter(self)
+        BlockKind::Delimiter(Arc::new(self))
+    }
+}
+
+/// Shared between every output block, rather than cloned into each
+impl From<Arc<Delimiter>> for BlockKind {
+    fn from(delim: Arc<Delimiter>) -> Self {
+        BlockKind::Delimiter(delim)
     }
 }
 
@@ -285,6 +360,12 @@ impl AsField for Delimiter {
     }
 }
 
+impl AsField for Arc<Delimiter> {
+    fn as_field(&self) -> Field {
+        Field::Delimiter
+    }
+}
+
 impl Default for Delimiter {
     fn default() -> Self {
         Delimiter::Char(',')
@@ -343,98 +424,679 @@ impl Default for Guard {
     }
 }
 
+/// Wrapper around the jmes type, which like JsonPointer
+/// is only assembled into a string when displayed
 #[derive(Debug, Clone)]
 pub struct JmesPath {
-    inner: String,
+    inner: PointerKind,
 }
 
 impl JmesPath {
-    pub fn from<P: Pointer>(p: &P) -> Self {
-        match p.as_parts() {
-            Ok(parts) => JmesPath {
-                inner: format!("{}", JmesDisplay::from(parts)),
-            },
-            Err(_) => {
-                warn!("Could not assemble jmespath... skipping");
-                JmesPath {
-                    inner: String::default(),
-                }
-            }
+    pub fn from(p: &PointerKind) -> Self {
+        JmesPath { inner: p.clone() }
+    }
+}
+
+impl std::fmt::Display for JmesPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.inner.fmt_path(f, PathSyntax::Jmes)
+    }
+}
+
+impl Into<BlockKind> for JmesPath {
+    fn into(self) -> BlockKind {
+        BlockKind::Jmes(self)
+    }
+}
+
+impl AsField for JmesPath {
+    fn as_field(&self) -> Field {
+        Field::JmesPath
+    }
+}
+
+/// Wrapper around the JSONPath type, i.e "$.a['b c'][0]"
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    inner: PointerKind,
+}
+
+impl JsonPath {
+    pub fn from(p: &PointerKind) -> Self {
+        JsonPath { inner: p.clone() }
+    }
+}
+
+impl std::fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.inner.fmt_path(f, PathSyntax::JsonPath)
+    }
+}
+
+impl Into<BlockKind> for JsonPath {
+    fn into(self) -> BlockKind {
+        BlockKind::JsonPath(self)
+    }
+}
+
+impl AsField for JsonPath {
+    fn as_field(&self) -> Field {
+        Field::JsonPath
+    }
+}
+
+/// Wrapper around the jq path type, i.e '.a["b c"][0]'
+#[derive(Debug, Clone)]
+pub struct JqPath {
+    inner: PointerKind,
+}
+
+impl JqPath {
+    pub fn from(p: &PointerKind) -> Self {
+        JqPath { inner: p.clone() }
+    }
+}
+
+impl std::fmt::Display for JqPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.inner.fmt_path(f, PathSyntax::Jq)
+    }
+}
+
+impl Into<BlockKind> for JqPath {
+    fn into(self) -> BlockKind {
+        BlockKind::Jq(self)
+    }
+}
+
+impl AsField for JqPath {
+    fn as_field(&self) -> Field {
+        Field::JqPath
+    }
+}
+
+/// The number of parts in the pointer, 0 being the root
+#[derive(Debug, Clone)]
+pub struct PointerDepth {
+    inner: PointerKind,
+}
+
+impl PointerDepth {
+    pub fn from(p: &PointerKind) -> Self {
+        PointerDepth { inner: p.clone() }
+    }
+}
+
+impl std::fmt::Display for PointerDepth {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.inner.depth())
+    }
+}
+
+impl Into<BlockKind> for PointerDepth {
+    fn into(self) -> BlockKind {
+        BlockKind::Depth(self)
+    }
+}
+
+impl AsField for PointerDepth {
+    fn as_field(&self) -> Field {
+        Field::Depth
+    }
+}
+
+/// The last part of the pointer, i.e the member's key or
+/// the element's index, unescaped. Empty for the root
+#[derive(Debug, Clone)]
+pub struct PointerKey {
+    inner: PointerKind,
+}
+
+impl PointerKey {
+    pub fn from(p: &PointerKind) -> Self {
+        PointerKey { inner: p.clone() }
+    }
+}
+
+impl std::fmt::Display for PointerKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.inner.last() {
+            Some(part) => write!(f, "{}", part),
+            None => Ok(()),
         }
     }
 }
 
-/// Effectively implements a specialized Display for Pointer
-struct JmesDisplay<'a>(&'a Vec<PointerParts>);
+impl Into<BlockKind> for PointerKey {
+    fn into(self) -> BlockKind {
+        BlockKind::Key(self)
+    }
+}
+
+impl AsField for PointerKey {
+    fn as_field(&self) -> Field {
+        Field::Key
+    }
+}
+
+/// The pointer of the containing object / array,
+/// empty for the root
+#[derive(Debug, Clone)]
+pub struct ParentPointer {
+    inner: PointerKind,
+}
 
-impl<'a> From<&'a Vec<PointerParts>> for JmesDisplay<'a> {
-    fn from(p: &'a Vec<PointerParts>) -> JmesDisplay<'a> {
-        JmesDisplay(p)
+impl ParentPointer {
+    pub fn from(p: &PointerKind) -> Self {
+        ParentPointer { inner: p.clone() }
     }
 }
 
-impl<'a> std::fmt::Display for JmesDisplay<'a> {
+impl std::fmt::Display for ParentPointer {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        // Check for unsupported JSON, i.e JSON literals
-        if !self.0.is_empty() {
-            for (first, _, item) in self
-                .0
-                .iter()
-                .filter(|part| match part {
-                    PointerParts::Slash => false,
-                    _ => true,
-                })
-                .identify_first_last()
-            {
-                match item {
-                    PointerParts::Object(s) => {
-                        if first {
-                            write!(f, "{}", s)?
-                        } else {
-                            write!(f, ".{}", s)?
-                        }
-                    }
-                    PointerParts::Array(u) => write!(f, "[{}]", u)?,
-                    PointerParts::Slash => {}
-                }
-            }
+        match self.inner.parent() {
+            Some(parent) => write!(f, "{}", parent),
+            None => Ok(()),
+        }
+    }
+}
 
-            Ok(())
-        } else {
-            // If unsupported, return what appears to be the standard response
-            write!(f, "null")
+impl Into<BlockKind> for ParentPointer {
+    fn into(self) -> BlockKind {
+        BlockKind::Parent(self)
+    }
+}
+
+impl AsField for ParentPointer {
+    fn as_field(&self) -> Field {
+        Field::Parent
+    }
+}
+
+/// The position of an array element,
+/// empty for anything else
+#[derive(Debug, Clone)]
+pub struct ArrayIndex {
+    inner: PointerKind,
+}
+
+impl ArrayIndex {
+    pub fn from(p: &PointerKind) -> Self {
+        ArrayIndex { inner: p.clone() }
+    }
+}
+
+impl std::fmt::Display for ArrayIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.inner.last() {
+            Some(PointerParts::Array(u)) => write!(f, "{}", u),
+            _ => Ok(()),
         }
     }
 }
 
-impl AsRef<str> for JmesPath {
-    fn as_ref(&self) -> &str {
-        &self.inner
+impl Into<BlockKind> for ArrayIndex {
+    fn into(self) -> BlockKind {
+        BlockKind::Index(self)
     }
 }
 
-impl std::fmt::Display for JmesPath {
+impl AsField for ArrayIndex {
+    fn as_field(&self) -> Field {
+        Field::Index
+    }
+}
+
+/// The path of the input a row was read from, '-' for stdin
+#[derive(Debug, Clone)]
+pub struct SourcePath {
+    inner: Arc<str>,
+}
+
+impl From<&Origin> for SourcePath {
+    fn from(origin: &Origin) -> Self {
+        SourcePath {
+            inner: Arc::clone(origin.source()),
+        }
+    }
+}
+
+impl std::fmt::Display for SourcePath {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{}", self.inner)
     }
 }
 
-impl Into<BlockKind> for JmesPath {
+impl Into<BlockKind> for SourcePath {
     fn into(self) -> BlockKind {
-        BlockKind::Jmes(self)
+        BlockKind::Source(self)
     }
 }
 
-impl AsField for JmesPath {
+impl AsField for SourcePath {
     fn as_field(&self) -> Field {
-        Field::JmesPath
+        Field::Source
     }
 }
 
-impl Default for JmesPath {
-    fn default() -> Self {
-        JmesPath {
-            inner: String::default(),
+/// The number of the record a row was read from,
+/// empty unless input is read line by line
+#[derive(Debug, Clone, Copy)]
+pub struct LineNumber {
+    inner: Option<usize>,
+}
+
+impl From<&Origin> for LineNumber {
+    fn from(origin: &Origin) -> Self {
+        LineNumber {
+            inner: origin.line(),
+        }
+    }
+}
+
+impl std::fmt::Display for LineNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.inner {
+            Some(line) => write!(f, "{}", line),
+            None => Ok(()),
         }
     }
 }
+
+impl Into<BlockKind> for LineNumber {
+    fn into(self) -> BlockKind {
+        BlockKind::Line(self)
+    }
+}
+
+impl AsField for LineNumber {
+    fn as_field(&self) -> Field {
+        Field::Line
+    }
+}
+
+/// Where a row's value starts in its input, in bytes
+#[derive(Debug, Clone, Copy)]
+pub struct ByteOffset {
+    inner: Option<u64>,
+}
+
+impl From<Option<&Span>> for ByteOffset {
+    fn from(span: Option<&Span>) -> Self {
+        ByteOffset {
+            inner: span.map(Span::offset),
+        }
+    }
+}
+
+impl std::fmt::Display for ByteOffset {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.inner {
+            Some(offset) => write!(f, "{}", offset),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Into<BlockKind> for ByteOffset {
+    fn into(self) -> BlockKind {
+        BlockKind::Offset(self)
+    }
+}
+
+impl AsField for ByteOffset {
+    fn as_field(&self) -> Field {
+        Field::Offset
+    }
+}
+
+/// How many bytes a row's value takes up in its input
+#[derive(Debug, Clone, Copy)]
+pub struct ByteLength {
+    inner: Option<u64>,
+}
+
+impl From<Option<&Span>> for ByteLength {
+    fn from(span: Option<&Span>) -> Self {
+        ByteLength {
+            inner: span.map(Span::len),
+        }
+    }
+}
+
+impl std::fmt::Display for ByteLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.inner {
+            Some(len) => write!(f, "{}", len),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Into<BlockKind> for ByteLength {
+    fn into(self) -> BlockKind {
+        BlockKind::Length(self)
+    }
+}
+
+impl AsField for ByteLength {
+    fn as_field(&self) -> Field {
+        Field::Length
+    }
+}
+
+/// A row's (1 based) position in the output, counted
+/// across every input as rows reach the writer
+#[derive(Debug, Clone, Copy)]
+pub struct RowNumber {
+    inner: usize,
+}
+
+impl From<usize> for RowNumber {
+    fn from(inner: usize) -> Self {
+        RowNumber { inner }
+    }
+}
+
+impl std::fmt::Display for RowNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+impl Into<BlockKind> for RowNumber {
+    fn into(self) -> BlockKind {
+        BlockKind::Row(self)
+    }
+}
+
+impl AsField for RowNumber {
+    fn as_field(&self) -> Field {
+        Field::Row
+    }
+}
+
+/// A row's value serialized as compact JSON, containers
+/// included, e.g '{"a":[1,2]}' or '"quoted"'
+#[derive(Debug, Clone)]
+pub struct RawJson {
+    inner: String,
+}
+
+impl From<&Json> for RawJson {
+    fn from(json: &Json) -> Self {
+        RawJson {
+            inner: json.to_string(),
+        }
+    }
+}
+
+/// Already serialized, see Subtree
+impl From<String> for RawJson {
+    fn from(inner: String) -> Self {
+        RawJson { inner }
+    }
+}
+
+impl std::fmt::Display for RawJson {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+impl Into<BlockKind> for RawJson {
+    fn into(self) -> BlockKind {
+        BlockKind::Raw(self)
+    }
+}
+
+impl AsField for RawJson {
+    fn as_field(&self) -> Field {
+        Field::Raw
+    }
+}
+
+/// The number of elements or members of an
+/// array or object, empty for any other value
+#[derive(Debug, Clone, Copy)]
+pub struct ContainerSize {
+    inner: Option<usize>,
+}
+
+impl ContainerSize {
+    pub fn of(json: &Json) -> Option<usize> {
+        match json {
+            jObject(map) => Some(map.len()),
+            jArray(a) => Some(a.len()),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Json> for ContainerSize {
+    fn from(json: &Json) -> Self {
+        ContainerSize {
+            inner: ContainerSize::of(json),
+        }
+    }
+}
+
+impl From<&Subtree> for ContainerSize {
+    fn from(subtree: &Subtree) -> Self {
+        ContainerSize {
+            inner: Some(subtree.size()),
+        }
+    }
+}
+
+impl std::fmt::Display for ContainerSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.inner {
+            Some(size) => write!(f, "{}", size),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Into<BlockKind> for ContainerSize {
+    fn into(self) -> BlockKind {
+        BlockKind::Size(self)
+    }
+}
+
+impl AsField for ContainerSize {
+    fn as_field(&self) -> Field {
+        Field::Size
+    }
+}
+
+/// The number of characters (i.e unicode scalar values)
+/// in a string, empty for any other value
+#[derive(Debug, Clone, Copy)]
+pub struct StringLength {
+    inner: Option<usize>,
+}
+
+impl From<&Json> for StringLength {
+    fn from(json: &Json) -> Self {
+        StringLength {
+            inner: match json {
+                jString(s) => Some(s.chars().count()),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for StringLength {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.inner {
+            Some(len) => write!(f, "{}", len),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Into<BlockKind> for StringLength {
+    fn into(self) -> BlockKind {
+        BlockKind::Len(self)
+    }
+}
+
+impl AsField for StringLength {
+    fn as_field(&self) -> Field {
+        Field::Len
+    }
+}
+
+/// Hex digest of a row's value in canonical form,
+/// see HashAlgorithm
+#[derive(Debug, Clone)]
+pub struct ContentHash {
+    inner: String,
+}
+
+impl From<String> for ContentHash {
+    fn from(inner: String) -> Self {
+        ContentHash { inner }
+    }
+}
+
+impl std::fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+impl Into<BlockKind> for ContentHash {
+    fn into(self) -> BlockKind {
+        BlockKind::Hash(self)
+    }
+}
+
+impl AsField for ContentHash {
+    fn as_field(&self) -> Field {
+        Field::Hash
+    }
+}
+
+/// What a string value appears to hold (a date, URL, etc),
+/// empty for any other value or unrecognized strings
+#[derive(Debug, Clone, Copy)]
+pub struct SemanticType {
+    inner: Option<SemType>,
+}
+
+impl From<&Json> for SemanticType {
+    fn from(json: &Json) -> Self {
+        SemanticType {
+            inner: match json {
+                jString(s) => SemType::classify(s),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for SemanticType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.inner {
+            Some(kind) => write!(f, "{}", kind),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Into<BlockKind> for SemanticType {
+    fn into(self) -> BlockKind {
+        BlockKind::SemType(self)
+    }
+}
+
+impl AsField for SemanticType {
+    fn as_field(&self) -> Field {
+        Field::SemType
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::from_str};
+
+    fn raw(json: &str) -> String {
+        RawJson::from(&from_str::<Json>(json).unwrap()).to_string()
+    }
+
+    #[test]
+    fn raw_json_compacts_containers() {
+        assert_eq!(
+            raw(r#"[ 1, [], {"a": [null, true]} ]"#),
+            r#"[1,[],{"a":[null,true]}]"#
+        );
+        assert_eq!(raw("{ }"), "{}");
+    }
+
+    #[test]
+    fn raw_json_escapes_strings() {
+        assert_eq!(raw(r#""plain""#), r#""plain""#);
+        assert_eq!(raw(r#""q\"\\\nA""#), r#""q\"\\\nA""#);
+        assert_eq!(raw(r#""é""#), r#""é""#);
+    }
+
+    #[test]
+    fn container_size_counts_children() {
+        let size = |json: &str| ContainerSize::from(&from_str::<Json>(json).unwrap()).to_string();
+        assert_eq!(size(r#"[1, [2, 3], {}]"#), "3");
+        assert_eq!(size(r#"{"a": 1, "b": {"c": 2}}"#), "2");
+        assert_eq!(size("[]"), "0");
+        assert_eq!(size("{}"), "0");
+        assert_eq!(size(r#""abc""#), "");
+        assert_eq!(size("null"), "");
+    }
+
+    #[test]
+    fn string_length_counts_chars() {
+        let len = |json: &str| StringLength::from(&from_str::<Json>(json).unwrap()).to_string();
+        assert_eq!(len(r#""abc""#), "3");
+        assert_eq!(len(r#""héllo""#), "5");
+        assert_eq!(len(r#""日本🦀""#), "3");
+        assert_eq!(len(r#""""#), "0");
+        assert_eq!(len("[1, 2]"), "");
+        assert_eq!(len("12"), "");
+    }
+
+    fn refined(number: &str) -> JType {
+        JType::refined(&from_str(number).unwrap())
+    }
+
+    #[test]
+    fn refined_numbers() {
+        assert!(matches!(refined("1"), JType::Integer));
+        assert!(matches!(refined("-3"), JType::Integer));
+        assert!(matches!(refined("1.50"), JType::Float));
+        assert!(matches!(refined("1e2"), JType::Float));
+        assert!(matches!(refined(r#""1""#), JType::String));
+    }
+
+    #[test]
+    fn integer_has_no_fraction_or_exponent() {
+        assert!(is_integer("1"));
+        assert!(is_integer("-3"));
+        assert!(is_integer("123456789012345678901234"));
+        assert!(!is_integer("1.50"));
+        assert!(!is_integer("1e2"));
+        assert!(!is_integer("-1E-2"));
+    }
+
+    #[test]
+    #[cfg(feature = "exact-numbers")]
+    fn refined_big_integers() {
+        assert!(matches!(refined("123456789012345678901234"), JType::BigInt));
+        assert!(matches!(
+            refined("-123456789012345678901234"),
+            JType::BigInt
+        ));
+        assert!(matches!(refined("1.50"), JType::Float));
+        assert!(matches!(refined("1e2"), JType::Float));
+    }
+}
@@ -1,18 +1,15 @@
-use {
-    crate::{
-        cli::ProgramArgs,
-        models::{
-            assets::RegexOptions,
-            block::BlockKind,
-            error::ErrorKind,
-            field::{AsField, Field},
-        },
+use crate::{
+    cli::ProgramArgs,
+    models::{
+        assets::RegexOptions,
+        block::{BlockKind, RowNumber},
+        error::ErrorKind,
+        field::{AsField, Field},
     },
-    fnv::FnvHashMap,
 };
 
 /// Interface for converting the collected output parts into
-/// output
+/// output, blocks are borrowed so that formatting never copies them
 pub trait Builder<D>
 where
     D: Into<Field>,
@@ -20,28 +17,63 @@ where
     type Block: std::fmt::Display;
     type Error;
 
-    fn build_with(&self, d: D) -> Result<Self::Block, Self::Error>;
+    fn build_with(&self, d: D) -> Result<&Self::Block, Self::Error>;
+
+    fn identifer(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn delimiter(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn guard(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn type_of(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn pointer(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn value(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn jmes(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn jsonpath(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn jq(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn depth(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn key(&self) -> Result<&Self::Block, Self::Error>;
 
-    fn identifer(&self) -> Result<Self::Block, Self::Error>;
+    fn parent(&self) -> Result<&Self::Block, Self::Error>;
 
-    fn delimiter(&self) -> Result<Self::Block, Self::Error>;
+    fn index(&self) -> Result<&Self::Block, Self::Error>;
 
-    fn guard(&self) -> Result<Self::Block, Self::Error>;
+    fn source(&self) -> Result<&Self::Block, Self::Error>;
 
-    fn type_of(&self) -> Result<Self::Block, Self::Error>;
+    fn line(&self) -> Result<&Self::Block, Self::Error>;
 
-    fn pointer(&self) -> Result<Self::Block, Self::Error>;
+    fn offset(&self) -> Result<&Self::Block, Self::Error>;
 
-    fn value(&self) -> Result<Self::Block, Self::Error>;
+    fn length(&self) -> Result<&Self::Block, Self::Error>;
 
-    fn jmes(&self) -> Result<Self::Block, Self::Error>;
+    fn row(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn raw(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn size(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn len(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn hash(&self) -> Result<&Self::Block, Self::Error>;
+
+    fn semtype(&self) -> Result<&Self::Block, Self::Error>;
 }
 
 /// Container for the various final parts
-/// used to assemble the program's output
+/// used to assemble the program's output.
+/// Each part has a fixed slot, so an Output
+/// never allocates beyond the parts themselves
+/// (see Slots for the exception)
 #[derive(Debug)]
 pub struct Output {
-    blocks: FnvHashMap<usize, BlockKind>,
+    blocks: Slots,
 }
 // 0 == ident
 // 1 == delimiter
@@ -50,34 +82,119 @@ pub struct Output {
 // 4 == jptr
 // 5 == value
 // 6 == jmes
-// Remember to update OutputBuilder's done() if you change these
+// 7 == jsonpath
+// 8 == jq
+// 9 == depth
+// 10 == key
+// 11 == parent
+// 12 == index
+// 13 == source
+// 14 == line
+// 15 == offset
+// 16 == length
+// 17 == row
+// 18 == raw
+// 19 == size
+// 20 == len
+// 21 == hash
+// 22 == semtype
+// Remember to update OutputBuilder's store_unchecked() and SLOTS if you change these
 impl Output {
     fn get_ident(&self) -> Option<&BlockKind> {
-        self.blocks.get(&0)
+        self.blocks.get(0)
     }
 
     fn get_delimiter(&self) -> Option<&BlockKind> {
-        self.blocks.get(&1)
+        self.blocks.get(1)
     }
 
     fn get_guard(&self) -> Option<&BlockKind> {
-        self.blocks.get(&2)
+        self.blocks.get(2)
     }
 
     fn get_type(&self) -> Option<&BlockKind> {
-        self.blocks.get(&3)
+        self.blocks.get(3)
     }
 
     fn get_pointer(&self) -> Option<&BlockKind> {
-        self.blocks.get(&4)
+        self.blocks.get(4)
     }
 
     fn get_value(&self) -> Option<&BlockKind> {
-        self.blocks.get(&5)
+        self.blocks.get(5)
     }
 
     fn get_jmes(&self) -> Option<&BlockKind> {
-        self.blocks.get(&6)
+        self.blocks.get(6)
+    }
+
+    fn get_jsonpath(&self) -> Option<&BlockKind> {
+        self.blocks.get(7)
+    }
+
+    fn get_jq(&self) -> Option<&BlockKind> {
+        self.blocks.get(8)
+    }
+
+    fn get_depth(&self) -> Option<&BlockKind> {
+        self.blocks.get(9)
+    }
+
+    fn get_key(&self) -> Option<&BlockKind> {
+        self.blocks.get(10)
+    }
+
+    fn get_parent(&self) -> Option<&BlockKind> {
+        self.blocks.get(11)
+    }
+
+    fn get_index(&self) -> Option<&BlockKind> {
+        self.blocks.get(12)
+    }
+
+    fn get_source(&self) -> Option<&BlockKind> {
+        self.blocks.get(13)
+    }
+
+    fn get_line(&self) -> Option<&BlockKind> {
+        self.blocks.get(14)
+    }
+
+    fn get_offset(&self) -> Option<&BlockKind> {
+        self.blocks.get(15)
+    }
+
+    fn get_length(&self) -> Option<&BlockKind> {
+        self.blocks.get(16)
+    }
+
+    fn get_row(&self) -> Option<&BlockKind> {
+        self.blocks.get(17)
+    }
+
+    fn get_raw(&self) -> Option<&BlockKind> {
+        self.blocks.get(18)
+    }
+
+    fn get_size(&self) -> Option<&BlockKind> {
+        self.blocks.get(19)
+    }
+
+    fn get_len(&self) -> Option<&BlockKind> {
+        self.blocks.get(20)
+    }
+
+    fn get_hash(&self) -> Option<&BlockKind> {
+        self.blocks.get(21)
+    }
+
+    fn get_semtype(&self) -> Option<&BlockKind> {
+        self.blocks.get(22)
+    }
+
+    /// Numbers the row, which can only happen once it reaches the writer
+    pub fn store_row(&mut self, row: RowNumber) {
+        self.blocks.set(17, BlockKind::Row(row))
     }
 }
 
@@ -87,85 +204,234 @@ where
 {
     type Block = BlockKind;
     type Error = ErrorKind;
-    fn build_with(&self, d: D) -> Result<Self::Block, Self::Error> {
+    fn build_with(&self, d: D) -> Result<&Self::Block, Self::Error> {
         match d.into() {
             f @ Field::Identifier => self
                 .get_ident()
-                .cloned()
                 .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
             f @ Field::Type => self
                 .get_type()
-                .cloned()
                 .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
             f @ Field::Pointer => self
                 .get_pointer()
-                .cloned()
                 .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
             f @ Field::Value => self
                 .get_value()
-                .cloned()
                 .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
             f @ Field::JmesPath => self
                 .get_jmes()
-                .cloned()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::JsonPath => self
+                .get_jsonpath()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::JqPath => self
+                .get_jq()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Depth => self
+                .get_depth()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Key => self
+                .get_key()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Parent => self
+                .get_parent()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Index => self
+                .get_index()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Source => self
+                .get_source()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Line => self
+                .get_line()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Offset => self
+                .get_offset()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Length => self
+                .get_length()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Row => self
+                .get_row()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Raw => self
+                .get_raw()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Size => self
+                .get_size()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Len => self
+                .get_len()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::Hash => self
+                .get_hash()
+                .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
+            f @ Field::SemType => self
+                .get_semtype()
                 .ok_or_else(|| ErrorKind::MissingField(format!("{}", f))),
             _ => unreachable!("Make sure clap only allows valid fields to hit this"),
         }
     }
 
-    fn identifer(&self) -> Result<Self::Block, Self::Error> {
+    fn identifer(&self) -> Result<&Self::Block, Self::Error> {
         self.get_ident()
-            .cloned()
             .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Identifier)))
     }
 
-    fn delimiter(&self) -> Result<Self::Block, Self::Error> {
+    fn delimiter(&self) -> Result<&Self::Block, Self::Error> {
         self.get_delimiter()
-            .cloned()
             .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Delimiter)))
     }
 
-    fn guard(&self) -> Result<Self::Block, Self::Error> {
+    fn guard(&self) -> Result<&Self::Block, Self::Error> {
         self.get_guard()
-            .cloned()
             .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Guard)))
     }
 
-    fn type_of(&self) -> Result<Self::Block, Self::Error> {
+    fn type_of(&self) -> Result<&Self::Block, Self::Error> {
         self.get_type()
-            .cloned()
             .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Type)))
     }
 
-    fn pointer(&self) -> Result<Self::Block, Self::Error> {
+    fn pointer(&self) -> Result<&Self::Block, Self::Error> {
         self.get_pointer()
-            .cloned()
             .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Pointer)))
     }
 
-    fn value(&self) -> Result<Self::Block, Self::Error> {
+    fn value(&self) -> Result<&Self::Block, Self::Error> {
         self.get_value()
-            .cloned()
             .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Value)))
     }
 
-    fn jmes(&self) -> Result<Self::Block, Self::Error> {
+    fn jmes(&self) -> Result<&Self::Block, Self::Error> {
         self.get_jmes()
-            .cloned()
-            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Value)))
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::JmesPath)))
+    }
+
+    fn jsonpath(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_jsonpath()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::JsonPath)))
+    }
+
+    fn jq(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_jq()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::JqPath)))
+    }
+
+    fn depth(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_depth()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Depth)))
+    }
+
+    fn key(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_key()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Key)))
+    }
+
+    fn parent(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_parent()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Parent)))
+    }
+
+    fn index(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_index()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Index)))
+    }
+
+    fn source(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_source()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Source)))
+    }
+
+    fn line(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_line()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Line)))
+    }
+
+    fn offset(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_offset()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Offset)))
+    }
+
+    fn length(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_length()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Length)))
+    }
+
+    fn row(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_row()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Row)))
+    }
+
+    fn raw(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_raw()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Raw)))
+    }
+
+    fn size(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_size()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Size)))
+    }
+
+    fn len(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_len()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Len)))
+    }
+
+    fn hash(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_hash()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::Hash)))
+    }
+
+    fn semtype(&self) -> Result<&Self::Block, Self::Error> {
+        self.get_semtype()
+            .ok_or_else(|| ErrorKind::MissingField(format!("{}", Field::SemType)))
+    }
+}
+
+/// The number of slots, one per part
+const SLOTS: usize = 23;
+/// The number of slots kept inline, the rest are boxed
+const INLINE_SLOTS: usize = 7;
+
+/// Every part's slot, the first INLINE_SLOTS (those of the
+/// original fields) are kept inline. Slots past them are only
+/// allocated once one of them is stored, so that adding fields
+/// doesn't grow each record, which is moved several times on
+/// its way to the writer
+#[derive(Debug, Default)]
+struct Slots {
+    inline: [Option<BlockKind>; INLINE_SLOTS],
+    boxed: Option<Box<[Option<BlockKind>; SLOTS - INLINE_SLOTS]>>,
+}
+
+impl Slots {
+    fn get(&self, slot: usize) -> Option<&BlockKind> {
+        match slot.checked_sub(INLINE_SLOTS) {
+            None => self.inline[slot].as_ref(),
+            Some(i) => self.boxed.as_ref().and_then(|boxed| boxed[i].as_ref()),
+        }
+    }
+
+    fn set(&mut self, slot: usize, block: BlockKind) {
+        match slot.checked_sub(INLINE_SLOTS) {
+            None => self.inline[slot] = Some(block),
+            Some(i) => self.boxed.get_or_insert_with(Default::default)[i] = Some(block),
+        }
     }
 }
 
 /// Used to build up an Output struct
 #[derive(Debug)]
 pub struct OutputBuilder {
-    blocks: [Option<BlockKind>; 7],
+    blocks: Slots,
 }
 
 impl OutputBuilder {
     pub fn new() -> Self {
-        let blocks: [Option<BlockKind>; 7] = Default::default();
-        Self { blocks }
+        Self {
+            blocks: Slots::default(),
+        }
     }
 
     // Checked storage of output fields
@@ -180,54 +446,42 @@ impl OutputBuilder {
     // Unchecked storage of output fields, should only be used if item was checked in some other way
     pub fn store_unchecked<T: AsField>(&mut self, item: Option<T>) {
         if let Some(i) = item {
-            match <T as Into<BlockKind>>::into(i) {
-                BlockKind::Ident(i) => self.blocks[0] = Some(BlockKind::Ident(i)),
-                BlockKind::Delimiter(i) => self.blocks[1] = Some(BlockKind::Delimiter(i)),
-                BlockKind::Guard(i) => self.blocks[2] = Some(BlockKind::Guard(i)),
-                BlockKind::Type(i) => self.blocks[3] = Some(BlockKind::Type(i)),
-                BlockKind::Pointer(i) => self.blocks[4] = Some(BlockKind::Pointer(i)),
-                BlockKind::Value(i) => self.blocks[5] = Some(BlockKind::Value(i)),
-                BlockKind::Jmes(i) => self.blocks[6] = Some(BlockKind::Jmes(i)),
-            }
+            let block = <T as Into<BlockKind>>::into(i);
+            let slot = match block {
+                BlockKind::Ident(_) => 0,
+                BlockKind::Delimiter(_) => 1,
+                BlockKind::Guard(_) => 2,
+                BlockKind::Type(_) => 3,
+                BlockKind::Pointer(_) => 4,
+                BlockKind::Value(_) => 5,
+                BlockKind::Jmes(_) => 6,
+                BlockKind::JsonPath(_) => 7,
+                BlockKind::Jq(_) => 8,
+                BlockKind::Depth(_) => 9,
+                BlockKind::Key(_) => 10,
+                BlockKind::Parent(_) => 11,
+                BlockKind::Index(_) => 12,
+                BlockKind::Source(_) => 13,
+                BlockKind::Line(_) => 14,
+                BlockKind::Offset(_) => 15,
+                BlockKind::Length(_) => 16,
+                BlockKind::Row(_) => 17,
+                BlockKind::Raw(_) => 18,
+                BlockKind::Size(_) => 19,
+                BlockKind::Len(_) => 20,
+                BlockKind::Hash(_) => 21,
+                BlockKind::SemType(_) => 22,
+            };
+            self.blocks.set(slot, block)
         }
     }
 
-    pub fn done(mut self) -> Output {
-        let mut blocks =
-            FnvHashMap::with_capacity_and_hasher(self.blocks.len(), Default::default());
-        for opt in &mut self.blocks {
-            if let Some(block) = opt.take() {
-                // Hardcoded usizes for keys
-                // If you change these YOU MUST UPDATE the
-                // store_unchecked method AND the get_xx
-                // functions in Output too
-                match block {
-                    i @ BlockKind::Ident(_) => {
-                        blocks.insert(0, i);
-                    }
-                    d @ BlockKind::Delimiter(_) => {
-                        blocks.insert(1, d);
-                    }
-                    g @ BlockKind::Guard(_) => {
-                        blocks.insert(2, g);
-                    }
-                    t @ BlockKind::Type(_) => {
-                        blocks.insert(3, t);
-                    }
-                    p @ BlockKind::Pointer(_) => {
-                        blocks.insert(4, p);
-                    }
-                    v @ BlockKind::Value(_) => {
-                        blocks.insert(5, v);
-                    }
-                    j @ BlockKind::Jmes(_) => {
-                        blocks.insert(6, j);
-                    }
-                }
-            };
+    // The slots used by store_unchecked are the ones
+    // expected by Output's get_xx functions
+    pub fn done(self) -> Output {
+        Output {
+            blocks: self.blocks,
         }
-
-        Output { blocks }
     }
 
     /// Function is designed to be used with a filter_map
@@ -236,40 +490,32 @@ impl OutputBuilder {
     pub fn check(self, regex: Option<&RegexOptions>) -> Option<Self> {
         match regex {
             Some(regex) => match regex.on_field() {
-                Field::Identifier => match self.blocks[0] {
-                    Some(BlockKind::Ident(ref i)) if !regex.pattern().is_match(&i.to_string()) => {
-                        None
-                    }
+                Field::Identifier => match self.blocks.get(0) {
+                    Some(BlockKind::Ident(i)) if !regex.pattern().is_match(&i.to_string()) => None,
                     _ => Some(self),
                 },
-                Field::Delimiter => match self.blocks[1] {
-                    Some(BlockKind::Delimiter(ref d))
-                        if !regex.pattern().is_match(&d.to_string()) =>
-                    {
+                Field::Delimiter => match self.blocks.get(1) {
+                    Some(BlockKind::Delimiter(d)) if !regex.pattern().is_match(&d.to_string()) => {
                         None
                     }
                     _ => Some(self),
                 },
-                Field::Guard => match self.blocks[2] {
-                    Some(BlockKind::Guard(ref g)) if !regex.pattern().is_match(&g.to_string()) => {
-                        None
-                    }
+                Field::Guard => match self.blocks.get(2) {
+                    Some(BlockKind::Guard(g)) if !regex.pattern().is_match(&g.to_string()) => None,
                     _ => Some(self),
                 },
-                Field::Type => match self.blocks[3] {
-                    Some(BlockKind::Type(ref t)) if !regex.pattern().is_match(&t.to_string()) => {
-                        None
-                    }
+                Field::Type => match self.blocks.get(3) {
+                    Some(BlockKind::Type(t)) if !regex.pattern().is_match(&t.to_string()) => None,
                     _ => Some(self),
                 },
-                Field::Pointer => match &self.blocks[4] {
-                    Some(BlockKind::Pointer(ref p)) if !regex.pattern().is_match(p.as_ref()) => {
+                Field::Pointer => match self.blocks.get(4) {
+                    Some(BlockKind::Pointer(p)) if !regex.pattern().is_match(&p.to_string()) => {
                         None
                     }
                     _ => Some(self),
                 },
-                Field::Value => match self.blocks[5] {
-                    Some(BlockKind::Value(ref o)) => match o.as_ref() {
+                Field::Value => match self.blocks.get(5) {
+                    Some(BlockKind::Value(o)) => match o.as_ref() {
                         Some(v) if !regex.pattern().is_match(v) => None,
                         // This arm excludes any jptrs that do not a have an (single) associated value
                         // i.e objects and arrays... for example
@@ -284,8 +530,74 @@ impl OutputBuilder {
                     },
                     _ => Some(self),
                 },
-                Field::JmesPath => match &self.blocks[6] {
-                    Some(BlockKind::Jmes(ref j)) if !regex.pattern().is_match(j.as_ref()) => None,
+                Field::JmesPath => match self.blocks.get(6) {
+                    Some(BlockKind::Jmes(j)) if !regex.pattern().is_match(&j.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::JsonPath => match self.blocks.get(7) {
+                    Some(BlockKind::JsonPath(j)) if !regex.pattern().is_match(&j.to_string()) => {
+                        None
+                    }
+                    _ => Some(self),
+                },
+                Field::JqPath => match self.blocks.get(8) {
+                    Some(BlockKind::Jq(j)) if !regex.pattern().is_match(&j.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Depth => match self.blocks.get(9) {
+                    Some(BlockKind::Depth(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Key => match self.blocks.get(10) {
+                    Some(BlockKind::Key(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Parent => match self.blocks.get(11) {
+                    Some(BlockKind::Parent(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Index => match self.blocks.get(12) {
+                    Some(BlockKind::Index(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Source => match self.blocks.get(13) {
+                    Some(BlockKind::Source(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Line => match self.blocks.get(14) {
+                    Some(BlockKind::Line(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Offset => match self.blocks.get(15) {
+                    Some(BlockKind::Offset(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Length => match self.blocks.get(16) {
+                    Some(BlockKind::Length(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                // Rows are only numbered by the writer, which checks them there
+                Field::Row => Some(self),
+                Field::Raw => match self.blocks.get(18) {
+                    Some(BlockKind::Raw(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Size => match self.blocks.get(19) {
+                    Some(BlockKind::Size(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Len => match self.blocks.get(20) {
+                    Some(BlockKind::Len(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::Hash => match self.blocks.get(21) {
+                    Some(BlockKind::Hash(p)) if !regex.pattern().is_match(&p.to_string()) => None,
+                    _ => Some(self),
+                },
+                Field::SemType => match self.blocks.get(22) {
+                    Some(BlockKind::SemType(p)) if !regex.pattern().is_match(&p.to_string()) => {
+                        None
+                    }
                     _ => Some(self),
                 },
             },
@@ -293,3 +605,90 @@ impl OutputBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::models::{
+            block::{Delimiter, Guard, JType, JsonValue, RawJson},
+            column::Column,
+            error::Result,
+            write_formatted_output,
+        },
+        std::sync::Arc,
+    };
+
+    #[test]
+    fn output_shares_delimiter() -> Result<()> {
+        let delim = Arc::new(Delimiter::from(";;"));
+        let mut buffer = Vec::new();
+        for _ in 0..3 {
+            let mut builder = OutputBuilder::new();
+            builder.store_unchecked(Some(Arc::clone(&delim)));
+            builder.store_unchecked(Some(Guard::from("'")));
+            builder.store_unchecked(Some(JType::Number));
+            builder.store_unchecked(Some(JsonValue::from(Some(String::from("1")))));
+            let output = builder.done();
+            assert_eq!(Arc::strong_count(&delim), 2);
+            write_formatted_output(
+                &mut buffer,
+                output,
+                &[Field::Type.into(), Field::Value.into()],
+            )?;
+        }
+
+        assert_eq!(Arc::strong_count(&delim), 1);
+        assert_eq!(
+            String::from_utf8_lossy(&buffer),
+            "'Number';;'1'\n".repeat(3)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn guard_doubled_in_every_cell() -> Result<()> {
+        let mut buffer = Vec::new();
+        let columns = [
+            Field::Raw.into(),
+            Field::Value.into(),
+            Column::Literal {
+                value: "say \"hi\"".into(),
+                alias: None,
+            },
+        ];
+        for guard in &["\"", ""] {
+            let mut builder = OutputBuilder::new();
+            builder.store_unchecked(Some(Arc::new(Delimiter::from(","))));
+            builder.store_unchecked(Some(Guard::from(*guard)));
+            builder.store_unchecked(Some(RawJson::from(r#"{"c":"d"}"#.to_string())));
+            builder.store_unchecked(Some(JsonValue::from(Some(String::from("6\" 2")))));
+            write_formatted_output(&mut buffer, builder.done(), &columns)?;
+        }
+
+        assert_eq!(
+            String::from_utf8_lossy(&buffer),
+            concat!(
+                "\"{\"\"c\"\":\"\"d\"\"}\",\"6\"\" 2\",\"say \"\"hi\"\"\"\n",
+                "{\"c\":\"d\"},6\" 2,say \"hi\"\n"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn slots_box_only_once_needed() {
+        let mut slots = Slots::default();
+        slots.set(3, BlockKind::Type(JType::Number));
+        assert!(slots.boxed.is_none());
+        slots.set(SLOTS - 1, BlockKind::Type(JType::Null));
+        assert!(slots.boxed.is_some());
+
+        assert_eq!(slots.get(3).map(|b| b.to_string()), Some("Number".into()));
+        assert_eq!(
+            slots.get(SLOTS - 1).map(|b| b.to_string()),
+            Some("Null".into())
+        );
+        assert!(slots.get(INLINE_SLOTS).is_none());
+    }
+}
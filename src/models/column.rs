@@ -0,0 +1,224 @@
+use crate::models::{
+    error::{ErrorKind, Result},
+    field::Field,
+};
+
+/// A single column of output, described by the format string
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    /// One of each row's fields
+    Field { field: Field, alias: Option<String> },
+    /// The same text on every row
+    Literal {
+        value: String,
+        alias: Option<String>,
+    },
+}
+
+impl Column {
+    /// The field this column outputs, if it isn't a literal
+    pub fn field(&self) -> Option<Field> {
+        match self {
+            Column::Field { field, .. } => Some(*field),
+            Column::Literal { .. } => None,
+        }
+    }
+
+    /// The column's name in a header: its alias if it has
+    /// one, otherwise the field's name or the literal itself
+    pub fn header(&self) -> &str {
+        match self {
+            Column::Field { alias: Some(a), .. } | Column::Literal { alias: Some(a), .. } => a,
+            Column::Field { field, .. } => (*field).into(),
+            Column::Literal { value, .. } => value,
+        }
+    }
+}
+
+impl From<Field> for Column {
+    fn from(field: Field) -> Self {
+        Column::Field { field, alias: None }
+    }
+}
+
+/// Parses a format string into its columns, allowing only the fields
+/// in whitelist. Columns are separated by '.' or ',' and each may be:
+///
+/// - A field, e.g 'jptr'
+/// - 'all', every field in the whitelist
+/// - A literal quoted with ' or ", e.g '"prod"'
+/// - A field prefixed by '-', excluding it wherever else it appears
+///
+/// Fields and literals can be given an alias for the header by
+/// suffixing ':alias', e.g 'jptr:path'. Separators and ':' inside
+/// quotes are taken as is, so literals and aliases may contain them
+pub fn parse_format(format: &str, whitelist: &[Field]) -> Result<Vec<Column>> {
+    let mut columns = Vec::new();
+    let mut excluded = Vec::new();
+    for item in split_unquoted(format, |c| c == '.' || c == ',')? {
+        let (term, alias) = match split_unquoted(item, |c| c == ':')?.as_slice() {
+            [term] => (*term, None),
+            [term, alias] if !alias.is_empty() => (*term, Some(unquote(alias).to_string())),
+            _ => {
+                return Err(ErrorKind::Message(format!(
+                    "'{}' is not a valid column, expected 'column' or 'column:alias'",
+                    item
+                ))
+                .into())
+            }
+        };
+        match (term, alias) {
+            ("", _) => {
+                return Err(
+                    ErrorKind::Message(format!("Empty column in format '{}'", format)).into(),
+                )
+            }
+            (t, None) if t.starts_with('-') => {
+                excluded.push(Field::try_from_whitelist(&t[1..], whitelist)?)
+            }
+            ("all", None) => columns.extend(whitelist.iter().copied().map(Column::from)),
+            (t, Some(_)) if t.starts_with('-') || t == "all" => {
+                return Err(ErrorKind::Message(format!(
+                    "'{}' cannot have an alias, only fields and literals can",
+                    t
+                ))
+                .into())
+            }
+            (t, alias) if is_quoted(t) => columns.push(Column::Literal {
+                value: unquote(t).to_string(),
+                alias,
+            }),
+            (t, alias) => columns.push(Column::Field {
+                field: Field::try_from_whitelist(t, whitelist)?,
+                alias,
+            }),
+        }
+    }
+    columns.retain(|c| c.field().map_or(true, |f| !excluded.contains(&f)));
+
+    match columns.is_empty() {
+        true => Err(ErrorKind::Message(format!("Format '{}' has no columns", format)).into()),
+        false => Ok(columns),
+    }
+}
+
+fn is_quoted(s: &str) -> bool {
+    s.len() >= 2
+        && ((s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')))
+}
+
+fn unquote(s: &str) -> &str {
+    match is_quoted(s) {
+        true => &s[1..s.len() - 1],
+        false => s,
+    }
+}
+
+/// Splits s on each char matching sep that isn't within quotes
+fn split_unquoted<F>(s: &str, sep: F) -> Result<Vec<&str>>
+where
+    F: Fn(char) -> bool,
+{
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if sep(c) => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            None => {}
+        }
+    }
+    if let Some(q) = quote {
+        return Err(ErrorKind::Message(format!("Unclosed {} in format '{}'", q, s)).into());
+    }
+    parts.push(&s[start..]);
+
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: [Field; 3] = [Field::Pointer, Field::Value, Field::JmesPath];
+
+    fn parse(format: &str) -> Result<Vec<Column>> {
+        parse_format(format, &FIELDS)
+    }
+
+    fn aliased(field: Field, alias: &str) -> Column {
+        Column::Field {
+            field,
+            alias: Some(alias.to_string()),
+        }
+    }
+
+    #[test]
+    fn format_all_and_exclusions() -> Result<()> {
+        assert_eq!(
+            parse("all")?,
+            FIELDS.iter().copied().map(Column::from).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            parse("all,-jmes")?,
+            vec![Field::Pointer.into(), Field::Value.into()]
+        );
+        assert_eq!(
+            parse("-value.all.value")?,
+            vec![Field::Pointer.into(), Field::JmesPath.into()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn format_literals_and_aliases() -> Result<()> {
+        let columns = parse(r#"jptr:path."prod".value:"a.b:c".'x,y':env"#)?;
+
+        assert_eq!(
+            columns,
+            vec![
+                aliased(Field::Pointer, "path"),
+                Column::Literal {
+                    value: "prod".to_string(),
+                    alias: None
+                },
+                aliased(Field::Value, "a.b:c"),
+                Column::Literal {
+                    value: "x,y".to_string(),
+                    alias: Some("env".to_string())
+                },
+            ]
+        );
+        assert_eq!(
+            columns.iter().map(Column::header).collect::<Vec<_>>(),
+            vec!["path", "prod", "a.b:c", "env"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn format_invalid() {
+        for format in &[
+            "",
+            "jptr.",
+            ".jptr",
+            "jptr..value",
+            "type",
+            "all:every",
+            "-jptr:path",
+            "-'lit'",
+            "jptr:",
+            "jptr:a:b",
+            "'unclosed.jptr",
+            "all.-jptr.-value.-jmes",
+        ] {
+            assert!(parse(format).is_err(), "{}", format);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use std::io::{Read, Result};
+
+/// ASCII record separator, used by RFC 7464 JSON text sequences
+pub const RECORD_SEPARATOR: u8 = 0x1E;
+
+/// Supported layouts of an input source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Detect the format by sniffing the input
+    Auto,
+    /// A single JSON document
+    Document,
+    /// One JSON document per line (NDJSON / JSON lines)
+    Lines,
+    /// Multiple JSON documents, one after another
+    Concatenated,
+    /// RFC 7464 JSON text sequences, each document prefixed with 0x1E
+    RecordSeparated,
+    /// A single top-level array of records
+    Array,
+}
+
+impl InputFormat {
+    /// Reads the first 'window' bytes of the input (or all of it, if
+    /// shorter) and guesses its format, returning the reason for the
+    /// guess and the bytes read, which must be read again before the
+    /// rest of the input. Pipes may hand over only a few bytes per
+    /// read, so reading continues until the window is full
+    pub fn detect<R: Read>(
+        input: &mut R,
+        window: usize,
+    ) -> Result<(InputFormat, &'static str, Vec<u8>)> {
+        let mut sniffed = Vec::with_capacity(window);
+        input.take(window as u64).read_to_end(&mut sniffed)?;
+        let (format, reason) = sniff(&sniffed);
+
+        Ok((format, reason, sniffed))
+    }
+}
+
+// Unchecked conversion, should only be used on
+// otherwise (clap) validated conversions
+impl From<&str> for InputFormat {
+    fn from(s: &str) -> Self {
+        match s {
+            "auto" => InputFormat::Auto,
+            "json" => InputFormat::Document,
+            "ndjson" => InputFormat::Lines,
+            "concat" => InputFormat::Concatenated,
+            "rs" => InputFormat::RecordSeparated,
+            "array" => InputFormat::Array,
+            _ => unreachable!("Called infallible conversion to InputFormat on an invalid &str"),
+        }
+    }
+}
+
+impl From<InputFormat> for &str {
+    fn from(f: InputFormat) -> Self {
+        match f {
+            InputFormat::Auto => "auto",
+            InputFormat::Document => "json",
+            InputFormat::Lines => "ndjson",
+            InputFormat::Concatenated => "concat",
+            InputFormat::RecordSeparated => "rs",
+            InputFormat::Array => "array",
+        }
+    }
+}
+
+impl std::fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", <&str>::from(*self))
+    }
+}
+
+/// Guesses the format of an input from its first chunk
+fn sniff(chunk: &[u8]) -> (InputFormat, &'static str) {
+    let chunk = chunk.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(chunk);
+    let start = match chunk.iter().position(|b| !b.is_ascii_whitespace()) {
+        Some(start) => start,
+        None => return (InputFormat::Document, "input is empty"),
+    };
+
+    if chunk[start] == RECORD_SEPARATOR {
+        return (
+            InputFormat::RecordSeparated,
+            "input begins with an ASCII record separator (0x1E)",
+        );
+    }
+
+    let is_array_of_records = chunk[start] == b'['
+        && chunk[start + 1..]
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            .map_or(false, |b| *b == b'{');
+
+    let end = match value_end(&chunk[start..]) {
+        Some(len) => start + len,
+        None if is_array_of_records => {
+            return (
+                InputFormat::Array,
+                "input begins with an array of objects larger than the sniffed chunk",
+            )
+        }
+        None => {
+            return (
+                InputFormat::Document,
+                "first value is larger than the sniffed chunk",
+            )
+        }
+    };
+
+    match chunk[end..].iter().position(|b| !b.is_ascii_whitespace()) {
+        None if is_array_of_records => (InputFormat::Array, "input is a single array of objects"),
+        None => (InputFormat::Document, "input contains a single value"),
+        Some(next) => {
+            let single_line = !chunk[start..end].contains(&b'\n');
+            let newline_between = chunk[end..end + next].contains(&b'\n');
+            if single_line && newline_between {
+                (
+                    InputFormat::Lines,
+                    "first value fits on one line and is followed by another value on a new line",
+                )
+            } else {
+                (
+                    InputFormat::Concatenated,
+                    "first value is followed by another value",
+                )
+            }
+        }
+    }
+}
+
+/// Returns the length of the first JSON value in buf,
+/// or None if it does not end inside buf
+fn value_end(buf: &[u8]) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for (i, b) in buf.iter().enumerate() {
+        match (in_quotes, b) {
+            (true, _) if escaped => escaped = false,
+            (true, b'\\') => escaped = true,
+            (true, b'"') => {
+                in_quotes = false;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            (true, _) => {}
+            (false, b'"') => in_quotes = true,
+            (false, b'{') | (false, b'[') => depth += 1,
+            (false, b'}') | (false, b']') => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            // Scalars (numbers, literals) end at the first whitespace or structural byte
+            (false, b) if depth == 0 && (b.is_ascii_whitespace() || b"{[,\x1E".contains(b)) => {
+                return Some(i)
+            }
+            (false, _) => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_document() {
+        assert_eq!(
+            sniff(b"{\n  \"a\": 1,\n  \"b\": [1, 2]\n}\n").0,
+            InputFormat::Document
+        );
+        assert_eq!(sniff(b"  \"just a string\"  ").0, InputFormat::Document);
+        assert_eq!(sniff(b"{\"a\": \"unterminated").0, InputFormat::Document);
+        assert_eq!(sniff(b"").0, InputFormat::Document);
+    }
+
+    #[test]
+    fn sniff_lines() {
+        assert_eq!(sniff(b"{\"a\":1}\n{\"b\":2}\n").0, InputFormat::Lines);
+        assert_eq!(sniff(b"{\"a\":1}\r\n{\"b\":2}\r\n").0, InputFormat::Lines);
+        assert_eq!(sniff(b"1\n2\n3\n").0, InputFormat::Lines);
+    }
+
+    #[test]
+    fn sniff_concatenated() {
+        assert_eq!(sniff(b"{\"a\":1}{\"b\":2}").0, InputFormat::Concatenated);
+        assert_eq!(
+            sniff(b"{\n\"a\":1\n}\n{\n\"b\":2\n}").0,
+            InputFormat::Concatenated
+        );
+    }
+
+    #[test]
+    fn sniff_record_separated() {
+        assert_eq!(
+            sniff(b"\x1E{\"a\":1}\n\x1E{\"b\":2}\n").0,
+            InputFormat::RecordSeparated
+        );
+    }
+
+    #[test]
+    fn sniff_array() {
+        assert_eq!(sniff(b"[{\"a\":1},{\"a\":2}]").0, InputFormat::Array);
+        assert_eq!(sniff(b"[ {\"a\":1}, {\"a\":").0, InputFormat::Array);
+        assert_eq!(sniff(b"[1, 2, 3]").0, InputFormat::Document);
+    }
+
+    /// Hands over a single byte per read, as a slow pipe might
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((b, rest)), Some(first)) => {
+                    *first = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn detect_fills_window() -> Result<()> {
+        let data = b"{\"a\":1}\n{\"b\":2}\n";
+        let mut input = Trickle(data);
+        let (format, _, sniffed) = InputFormat::detect(&mut input, 12)?;
+
+        assert_eq!(format, InputFormat::Lines);
+        assert_eq!(sniffed, &data[..12]);
+        assert_eq!(input.0, &data[12..]);
+        Ok(())
+    }
+
+    #[test]
+    fn sniff_quoted_structure() {
+        assert_eq!(
+            sniff(b"{\"a\":\"}\\\"{\\n\"}\n{\"b\":2}").0,
+            InputFormat::Lines
+        );
+    }
+}
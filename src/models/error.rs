@@ -76,8 +76,8 @@ impl Error {
         type E = ErrorKind;
         type C = Context;
         match (err, con) {
-            (E::Io(e), C::DataLenEqualLineBufferLen(s)) => {
-                write!(f, "Input line size is >= input buffer. Input buffer size is adjustable, try adding 'config --buf_in <num>' where <num> > {} (error: {})", s, e)
+            (E::Io(e), C::LineExceedsBufferMax(line, max)) => {
+                write!(f, "Line {} is larger than the maximum input buffer ({} bytes). The maximum is adjustable, try adding 'config --buf_in_max <num>' (error: {})", line, max, e)
             }
             (E::ChannelError, C::UnexpectedMetaChannelClose(n)) => {
                 write!(f, "A meta channel ({}) closed unexpectedly, normally due to a SIGINT", n)
@@ -96,7 +96,7 @@ impl Error {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Context {
     Override(String),
-    DataLenEqualLineBufferLen(usize),
+    LineExceedsBufferMax(usize, usize),
     UnexpectedMetaChannelClose(String),
     UnexpectedDataChannelClose,
     ThreadPanic(String),
@@ -122,9 +122,9 @@ impl Context {
         Some(Self::ThreadPanic(format!("{}", thread_name)))
     }
 
-    /// DataLenEqualLineBufferLen
-    pub fn dlelbl(data_len: usize) -> Option<Self> {
-        Some(Context::DataLenEqualLineBufferLen(data_len))
+    /// LineExceedsBufferMax
+    pub fn lebm(line: usize, max: usize) -> Option<Self> {
+        Some(Context::LineExceedsBufferMax(line, max))
     }
 }
 
@@ -32,6 +32,22 @@ pub enum Field {
     Pointer,
     Value,
     JmesPath,
+    JsonPath,
+    JqPath,
+    Depth,
+    Key,
+    Parent,
+    Index,
+    Source,
+    Line,
+    Offset,
+    Length,
+    Row,
+    Raw,
+    Size,
+    Len,
+    Hash,
+    SemType,
 }
 
 impl Field {
@@ -46,6 +62,22 @@ impl Field {
             "jptr" => Ok(Field::Pointer),
             "value" => Ok(Field::Value),
             "jmes" => Ok(Field::JmesPath),
+            "jsonpath" => Ok(Field::JsonPath),
+            "jq" => Ok(Field::JqPath),
+            "depth" => Ok(Field::Depth),
+            "key" => Ok(Field::Key),
+            "parent" => Ok(Field::Parent),
+            "index" => Ok(Field::Index),
+            "source" => Ok(Field::Source),
+            "line" => Ok(Field::Line),
+            "offset" => Ok(Field::Offset),
+            "length" => Ok(Field::Length),
+            "row" => Ok(Field::Row),
+            "raw" => Ok(Field::Raw),
+            "size" => Ok(Field::Size),
+            "len" => Ok(Field::Len),
+            "hash" => Ok(Field::Hash),
+            "semtype" => Ok(Field::SemType),
             _ => Err(ErrorKind::Message(format!("'{}' is not a valid field", s)).into()),
         }
     }
@@ -97,6 +129,22 @@ impl From<&str> for Field {
             "jptr" => Field::Pointer,
             "value" => Field::Value,
             "jmes" => Field::JmesPath,
+            "jsonpath" => Field::JsonPath,
+            "jq" => Field::JqPath,
+            "depth" => Field::Depth,
+            "key" => Field::Key,
+            "parent" => Field::Parent,
+            "index" => Field::Index,
+            "source" => Field::Source,
+            "line" => Field::Line,
+            "offset" => Field::Offset,
+            "length" => Field::Length,
+            "row" => Field::Row,
+            "raw" => Field::Raw,
+            "size" => Field::Size,
+            "len" => Field::Len,
+            "hash" => Field::Hash,
+            "semtype" => Field::SemType,
             _ => unreachable!("Called infallible conversion to Field on a fallible conversion, use Field::try_from instead"),
         }
     }
@@ -112,6 +160,22 @@ impl From<Field> for &str {
             Field::Pointer => "jptr",
             Field::Value => "value",
             Field::JmesPath => "jmes",
+            Field::JsonPath => "jsonpath",
+            Field::JqPath => "jq",
+            Field::Depth => "depth",
+            Field::Key => "key",
+            Field::Parent => "parent",
+            Field::Index => "index",
+            Field::Source => "source",
+            Field::Line => "line",
+            Field::Offset => "offset",
+            Field::Length => "length",
+            Field::Row => "row",
+            Field::Raw => "raw",
+            Field::Size => "size",
+            Field::Len => "len",
+            Field::Hash => "hash",
+            Field::SemType => "semtype",
         }
     }
 }
@@ -126,6 +190,22 @@ impl<T: AsRef<BlockKind>> From<T> for Field {
             BlockKind::Pointer(_) => Field::Pointer,
             BlockKind::Value(_) => Field::Value,
             BlockKind::Jmes(_) => Field::JmesPath,
+            BlockKind::JsonPath(_) => Field::JsonPath,
+            BlockKind::Jq(_) => Field::JqPath,
+            BlockKind::Depth(_) => Field::Depth,
+            BlockKind::Key(_) => Field::Key,
+            BlockKind::Parent(_) => Field::Parent,
+            BlockKind::Index(_) => Field::Index,
+            BlockKind::Source(_) => Field::Source,
+            BlockKind::Line(_) => Field::Line,
+            BlockKind::Offset(_) => Field::Offset,
+            BlockKind::Length(_) => Field::Length,
+            BlockKind::Row(_) => Field::Row,
+            BlockKind::Raw(_) => Field::Raw,
+            BlockKind::Size(_) => Field::Size,
+            BlockKind::Len(_) => Field::Len,
+            BlockKind::Hash(_) => Field::Hash,
+            BlockKind::SemType(_) => Field::SemType,
         }
     }
 }
@@ -140,6 +220,22 @@ impl std::fmt::Display for Field {
             Field::Pointer => write!(f, "jptr"),
             Field::Value => write!(f, "value"),
             Field::JmesPath => write!(f, "jmes"),
+            Field::JsonPath => write!(f, "jsonpath"),
+            Field::JqPath => write!(f, "jq"),
+            Field::Depth => write!(f, "depth"),
+            Field::Key => write!(f, "key"),
+            Field::Parent => write!(f, "parent"),
+            Field::Index => write!(f, "index"),
+            Field::Source => write!(f, "source"),
+            Field::Line => write!(f, "line"),
+            Field::Offset => write!(f, "offset"),
+            Field::Length => write!(f, "length"),
+            Field::Row => write!(f, "row"),
+            Field::Raw => write!(f, "raw"),
+            Field::Size => write!(f, "size"),
+            Field::Len => write!(f, "len"),
+            Field::Hash => write!(f, "hash"),
+            Field::SemType => write!(f, "semtype"),
         }
     }
 }
@@ -0,0 +1,170 @@
+use {
+    crate::models::subtree::{Subtree, Subtrees},
+    serde_json::{
+        to_writer, Value as Json,
+        Value::{
+            Array as jArray, Bool as jBool, Null as jNull, Number as jNumber, Object as jObject,
+            String as jString,
+        },
+    },
+    sha2::{Digest, Sha256},
+    std::{fmt::Write, io::Write as ioWrite},
+    xxhash_rust::xxh3::xxh3_64,
+};
+
+/// Supported algorithms for hashing row contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    /// 64 bit XXH3, fast but not cryptographic
+    #[default]
+    Xxh3,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /// Hashes the canonical form of a JSON value,
+    /// returning the digest as lowercase hex
+    pub fn digest(self, json: &Json) -> String {
+        self.digest_with(json, None)
+    }
+
+    /// As digest, but taking the digest of any child
+    /// found in children as is, rather than hashing it
+    pub fn digest_with(self, json: &Json, children: Option<&Subtrees>) -> String {
+        let mut canonical = Vec::new();
+        self.canonicalize(json, children, &mut canonical);
+
+        match self {
+            HashAlgorithm::Xxh3 => format!("{:016x}", xxh3_64(&canonical)),
+            HashAlgorithm::Sha256 => {
+                Sha256::digest(&canonical)
+                    .iter()
+                    .fold(String::with_capacity(64), |mut hex, b| {
+                        let _ = write!(hex, "{:02x}", b);
+                        hex
+                    })
+            }
+        }
+    }
+
+    /// Writes a scalar as compact JSON, and a container as the digests
+    /// of its children, i.e '[<digest>,<digest>]' or '{"key":<digest>}'
+    /// with its keys sorted. Equal values are thus written the same,
+    /// whatever order their keys were in, and a container's digest
+    /// only needs those of its children
+    fn canonicalize(self, json: &Json, children: Option<&Subtrees>, buf: &mut Vec<u8>) {
+        match json {
+            jObject(map) => {
+                let mut members: Vec<_> = map.iter().collect();
+                members.sort_by(|a, b| a.0.cmp(b.0));
+                buf.push(b'{');
+                for (i, (k, v)) in members.into_iter().enumerate() {
+                    if i > 0 {
+                        buf.push(b',')
+                    }
+                    // Writing to a Vec can't fail
+                    let _ = to_writer(&mut *buf, k);
+                    buf.push(b':');
+                    match children.and_then(|c| c.member(k)).and_then(Subtree::hash) {
+                        Some(digest) => buf.extend_from_slice(digest.as_bytes()),
+                        None => buf.extend_from_slice(self.digest(v).as_bytes()),
+                    }
+                }
+                buf.push(b'}');
+            }
+            jArray(a) => {
+                buf.push(b'[');
+                for (i, v) in a.iter().enumerate() {
+                    if i > 0 {
+                        buf.push(b',')
+                    }
+                    match children.and_then(|c| c.element(i)).and_then(Subtree::hash) {
+                        Some(digest) => buf.extend_from_slice(digest.as_bytes()),
+                        None => buf.extend_from_slice(self.digest(v).as_bytes()),
+                    }
+                }
+                buf.push(b']');
+            }
+            jString(s) => {
+                let _ = to_writer(&mut *buf, s);
+            }
+            jNumber(n) => {
+                let _ = write!(buf, "{}", n);
+            }
+            jBool(b) => {
+                let _ = write!(buf, "{}", b);
+            }
+            jNull => buf.extend_from_slice(b"null"),
+        }
+    }
+}
+
+// Unchecked conversion, should only be used on
+// otherwise (clap) validated conversions
+impl From<&str> for HashAlgorithm {
+    fn from(s: &str) -> Self {
+        match s {
+            "xxh3" => HashAlgorithm::Xxh3,
+            "sha256" => HashAlgorithm::Sha256,
+            _ => unreachable!("Called infallible conversion to HashAlgorithm on an invalid &str"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::from_str};
+
+    fn canonical(json: &str) -> String {
+        let mut buf = Vec::new();
+        HashAlgorithm::Xxh3.canonicalize(&from_str(json).unwrap(), None, &mut buf);
+
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn digest(json: &str) -> String {
+        HashAlgorithm::Xxh3.digest(&from_str(json).unwrap())
+    }
+
+    #[test]
+    fn canonical_form_sorts_keys() {
+        assert_eq!(canonical(r#" "x\"y" "#), r#""x\"y""#);
+        assert_eq!(
+            canonical(r#"{ "b": [1, {"d": null}], "a": true }"#),
+            format!(
+                r#"{{"a":{},"b":{}}}"#,
+                digest("true"),
+                digest(r#"[1,{"d":null}]"#)
+            )
+        );
+        assert_eq!(
+            canonical(r#"[1, {"d": null}]"#),
+            format!("[{},{}]", digest("1"), digest(r#"{"d":null}"#))
+        );
+    }
+
+    #[test]
+    fn digest_ignores_key_order() {
+        for algo in &[HashAlgorithm::Xxh3, HashAlgorithm::Sha256] {
+            let a = algo.digest(&from_str(r#"{"a": 1, "b": [2]}"#).unwrap());
+            let b = algo.digest(&from_str(r#"{"b":[2],"a":1}"#).unwrap());
+            let c = algo.digest(&from_str(r#"{"a": 1, "b": [3]}"#).unwrap());
+
+            assert_eq!(a, b);
+            assert_ne!(a, c);
+        }
+    }
+
+    #[test]
+    fn digest_distinguishes_types() {
+        let number = HashAlgorithm::Sha256.digest(&from_str("1").unwrap());
+        let string = HashAlgorithm::Sha256.digest(&from_str(r#""1""#).unwrap());
+
+        assert_eq!(
+            number,
+            "6b86b273ff34fce19d6b804eff5a3f5747ada4eaa22f1d49c01e52ddb7875b4b"
+        );
+        assert_ne!(number, string);
+        assert_eq!(HashAlgorithm::Xxh3.digest(&jNull).len(), 16);
+    }
+}
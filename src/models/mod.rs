@@ -3,19 +3,26 @@ use {
         cli::ProgramArgs,
         models::{
             assets::{IdentifyFirstLast, ReadFrom, ReadKind, RegexOptions},
-            block::Identifier,
+            batch::BatchSender,
+            block::{Delimiter, Guard, Identifier},
             builder::{Builder, Output},
-            error::{Context, ErrContext, ErrorKind, Result},
+            column::Column,
+            error::{ErrorKind, Result},
             field::Field,
+            origin::Origin,
             pointer::{Pointer, PointerKind},
             scan::JsonScan,
+            subtree::{Subtree, Subtrees},
         },
         with_log, CLI,
     },
+    memchr::memchr,
     simplelog::*,
     std::{
+        borrow::Cow,
+        fmt::Write as fmtWrite,
         fs::{File, OpenOptions},
-        io::{stdin as cin, stdout as cout, Result as ioResult, Write as ioWrite},
+        io::{stdin as cin, stdout as cout, BufRead, Write as ioWrite},
         path::PathBuf,
         str::from_utf8,
         sync::mpsc::SyncSender,
@@ -23,17 +30,34 @@ use {
 };
 
 pub mod assets;
+pub mod batch;
 pub mod block;
 pub mod builder;
+pub mod column;
+pub mod detect;
 pub mod error;
 pub mod field;
+pub mod hash;
+pub mod origin;
 pub mod pointer;
+pub mod record;
 pub mod scan;
+pub mod semtype;
+pub mod sort;
+pub mod subtree;
 
 /// Type def for the reader -> builder channel
-pub type ToBuilder = (Option<Identifier>, Option<PointerKind>, Option<Vec<u8>>);
+pub type ToBuilder = (
+    Option<Identifier>,
+    Option<PointerKind>,
+    Option<Vec<u8>>,
+    Option<Origin>,
+    Option<Subtrees>,
+);
 /// Type def for the builder -> writer channel
 pub type ToWriter = Output;
+/// Batches packets before sending them to a builder thread
+pub type ToBuilderTx = BatchSender<ToBuilder, SyncSender<Vec<ToBuilder>>>;
 
 /// Determines write destination from runtime args
 // w: (_, bool), true => append, false => create
@@ -85,7 +109,10 @@ pub fn set_reader(src: &Option<ReadFrom>) -> ReadKind {
         Some(s) => match s {
             ReadFrom::File(path) => with_log!(
                 match File::open(path) {
-                    Ok(f) => with_log!(ReadKind::File(f), info!("Success! ({:?})", path)),
+                    Ok(f) => with_log!(
+                        ReadKind::File(f, path.clone()),
+                        info!("Success! ({:?})", path)
+                    ),
                     Err(e) => with_log!(
                         ReadKind::Stdin(cin()),
                         warn!("Failed! {}, switching to stdin... ({:?})", e, path)
@@ -169,211 +196,569 @@ pub fn initialize_logging(opts: &ProgramArgs) {
 }
 
 /// Entry function for the JSON stream parser,
-/// handles both recursive and single item docs
-pub fn unwind_json<I>(
+/// handles both recursive and single item docs.
+/// If split is Some, each element of the array at
+/// that path is unwound as a doc of its own, see
+/// unwind_split for how count identifies them
+#[allow(clippy::too_many_arguments)]
+pub fn unwind_json<R>(
     opts: &ProgramArgs,
     ident: Option<usize>,
-    source: Option<I>,
-    channel: SyncSender<ToBuilder>,
+    origin: Option<&Origin>,
+    source: Option<R>,
+    split: Option<&[String]>,
+    mut count: Option<&mut usize>,
+    channel: &mut ToBuilderTx,
 ) -> Result<()>
 where
-    I: Iterator<Item = ioResult<u8>>,
+    R: BufRead,
 {
     debug!("Started parsing a JSON doc");
     let pointer = eval_raw(|opts, _| PointerKind::new(opts), ());
     let mut maybe_scanner = source.map(JsonScan::new);
-    match maybe_scanner.as_mut() {
-        Some(scanner) => loop {
-            match scanner.next() {
-                Some(Ok(b @ b'[')) => {
-                    unwind_recursive(opts, ident, scanner, pointer.clone(), channel.clone(), b)?;
+    match (maybe_scanner.as_mut(), split) {
+        (Some(scanner), Some(path)) => {
+            while let Some(b) = scanner.next_token()? {
+                let count = count.as_deref_mut();
+                unwind_split(opts, ident, origin, scanner, path, channel, b, count)?;
+            }
+        }
+        (Some(scanner), None) => loop {
+            match scanner.next_byte()? {
+                Some(b @ b'[') => {
+                    let jptr = pointer.clone();
+                    unwind_recursive(opts, ident, origin, scanner, jptr, channel, b, 0)?;
                     continue;
                 }
-                Some(Ok(b @ b'{')) => {
-                    unwind_recursive(opts, ident, scanner, pointer.clone(), channel.clone(), b)?;
+                Some(b @ b'{') => {
+                    let jptr = pointer.clone();
+                    unwind_recursive(opts, ident, origin, scanner, jptr, channel, b, 0)?;
                     continue;
                 }
-                Some(Ok(b @ b'-')) | Some(Ok(b @ b'0'..=b'9')) => {
-                    unwind_single(opts, ident, scanner, b, channel.clone())?
+                Some(b @ b'-') | Some(b @ b'0'..=b'9') => {
+                    unwind_single(opts, ident, origin, scanner, b, channel)?
                 }
-                Some(Ok(b @ b't')) | Some(Ok(b @ b'f')) => {
-                    unwind_single(opts, ident, scanner, b, channel.clone())?
+                Some(b @ b't') | Some(b @ b'f') => {
+                    unwind_single(opts, ident, origin, scanner, b, channel)?
                 }
-                Some(Ok(b @ b'n')) => unwind_single(opts, ident, scanner, b, channel.clone())?,
-                Some(Ok(b @ b'"')) => unwind_single(opts, ident, scanner, b, channel.clone())?,
-                Some(Ok(_)) => continue,
-                Some(Err(e)) => return Err(e.into()),
+                Some(b @ b'n') => unwind_single(opts, ident, origin, scanner, b, channel)?,
+                Some(b @ b'"') => unwind_single(opts, ident, origin, scanner, b, channel)?,
+                Some(_) => continue,
                 None => break,
             }
         },
-        None => {
-            channel
-                .send((ident.map(|i| i.into()), PointerKind::new(opts), None))
-                .context(Context::udcc())?;
-
-            drop(channel);
+        (None, _) => {
+            channel.send((
+                ident.map(|i| i.into()),
+                PointerKind::new(opts),
+                None,
+                origin.cloned(),
+                None,
+            ))?;
         }
     }
+    // Don't leave the end of this doc waiting on the next
+    channel.flush()?;
 
     debug!("Finished parsing a JSON doc");
     Ok(())
 }
+/// Unwinds each top-level value of a stream of concatenated JSON docs
+/// as a doc of its own, scalars included. Docs are identified by their
+/// (1 based) position in the stream. If split is Some, each doc is
+/// split as in unwind_json
+pub fn unwind_concatenated<R>(
+    opts: &ProgramArgs,
+    ident: Option<usize>,
+    origin: Option<&Origin>,
+    source: R,
+    split: Option<&[String]>,
+    channel: &mut ToBuilderTx,
+) -> Result<()>
+where
+    R: BufRead,
+{
+    let mut scanner = JsonScan::new(source);
+    let mut doc = 0usize;
+    let mut next = scanner.next_token()?;
+    while let Some(b) = next {
+        doc += 1;
+        let ident = ident.map(|_| doc);
+        if !check_index(opts.regex(), ident) {
+            debug!("Skipping doc {}...", doc);
+            next = skip_value(&mut scanner, b)?;
+            continue;
+        }
+        debug!("Started parsing doc {} of a concatenated stream", doc);
+        next = match (b, split) {
+            (b @ b'[', Some(path)) | (b @ b'{', Some(path)) => {
+                let scanner = &mut scanner;
+                // Elements keep the ident of their doc
+                unwind_split(opts, ident, origin, scanner, path, channel, b, None)?;
+                scanner.next_token()?
+            }
+            (b, Some(_)) => {
+                debug!("Skipping a scalar doc, which has no array to split...");
+                skip_value(&mut scanner, b)?
+            }
+            (b @ b'[', None) | (b @ b'{', None) => {
+                let jptr = PointerKind::new(opts);
+                unwind_recursive(opts, ident, origin, &mut scanner, jptr, channel, b, 0)?;
+                scanner.next_token()?
+            }
+            (b, None) => {
+                let start = scanner.position() - 1;
+                let (buffer, terminator) = read_scalar(&mut scanner, b)?;
+                channel.send((
+                    ident.map(|i| i.into()),
+                    PointerKind::new(opts),
+                    Some(buffer),
+                    origin.map(|o| o.at(start, Vec::new())),
+                    None,
+                ))?;
+                terminator_or_next(&mut scanner, terminator)?
+            }
+        };
+        // As with line delimited docs, don't leave one waiting on the next
+        channel.flush()?;
+    }
+
+    Ok(())
+}
+
 /// Recursively unwinds a JSON data stream
-/// sending the pieces to a builder thread
+/// sending the pieces to a builder thread.
+/// Returns the unwound container's Subtree,
+/// if its parent's row will need one
 // This function does not check if the stream is valid JSON,
 // if it isn't the deserializer will catch it,
 // but the error it emits might be cryptic depending on
 // how badly this function mangled it
-pub fn unwind_recursive<I>(
+#[allow(clippy::too_many_arguments)]
+pub fn unwind_recursive<R>(
     opts: &ProgramArgs,
     ident: Option<usize>,
-    scanner: &mut JsonScan<I>,
+    origin: Option<&Origin>,
+    scanner: &mut JsonScan<R>,
     jptr: Option<PointerKind>,
-    channel: SyncSender<ToBuilder>,
+    channel: &mut ToBuilderTx,
     prefix_byte: u8,
-) -> Result<()>
+    depth: usize,
+) -> Result<Option<Subtree>>
 where
-    I: Iterator<Item = ioResult<u8>>,
+    R: BufRead,
 {
     // Handle the '{' or '[' byte that the outside function might have
     let mut buffer: Vec<u8> = vec![prefix_byte];
+    let start = scanner.position() - 1;
+    // Roots never have a row of their own, so aren't summarized
+    let summarize = depth > 0 && Subtree::is_needed(opts);
+    // The entire doc is sent as is if output must follow the source order
+    if opts.source_order() {
+        let size = consume_container(scanner, |slice| buffer.extend_from_slice(slice))?;
+        let subtree = match summarize {
+            true => Some(Subtree::of(opts, size, &buffer, None)?),
+            false => None,
+        };
+        channel.send((
+            ident.map(|i| i.into()),
+            jptr,
+            Some(buffer),
+            origin.map(|o| o.at(start, Vec::new())),
+            None,
+        ))?;
+
+        return Ok(subtree);
+    }
+    // Summaries of the child containers, which are
+    // collapsed before their parent's row is built
+    let mut children = match Subtree::is_needed(opts) {
+        true => Some(Subtrees::new(prefix_byte)),
+        false => None,
+    };
+    // Where each child container was in the input, so that the
+    // builder can map the remaining bytes back to theirs
+    let mut collapsed = Vec::new();
     let mut array_count = 0usize;
     trace!("BEFORE: ({:?}, {:?})", &jptr, from_utf8(&buffer));
     loop {
-        match scanner.next() {
-            Some(Ok(b @ b'[')) if scanner.outside_quotes() => {
-                unwind_recursive(
-                    opts,
-                    ident,
-                    scanner,
-                    match prefix_byte {
-                        b'[' => jptr.as_ref().map(|ptr| ptr.clone_extend(array_count)),
-                        b'{' => jptr
-                            .as_ref()
-                            .map(|ptr| {
-                                from_utf8(&calculate_key(&buffer, scanner.offsets()))
-                                    .map(|s| ptr.clone_extend(s))
-                            })
-                            .transpose()?,
-                        _ => unreachable!(),
-                    },
-                    channel.clone(),
-                    b,
-                )?;
-                buffer.push(b);
-                // Recursive call above eats the corresponding ']' replace it, creating an empty array
-                buffer.push(b']');
-            }
-            Some(Ok(b @ b'{')) if scanner.outside_quotes() => {
-                unwind_recursive(
+        // Everything up to the next structural byte is copied as is
+        match scanner.read_until_structural(|slice| buffer.extend_from_slice(slice))? {
+            Some(b @ b'[') | Some(b @ b'{') => {
+                let child = scanner.position() - 1;
+                let key = match prefix_byte {
+                    b'{' if jptr.is_some() || children.is_some() => {
+                        Some(decode_key(calculate_key(&buffer))?)
+                    }
+                    _ => None,
+                };
+                let subtree = unwind_recursive(
                     opts,
                     ident,
+                    origin,
                     scanner,
                     match prefix_byte {
                         b'[' => jptr.as_ref().map(|ptr| ptr.clone_extend(array_count)),
                         b'{' => jptr
                             .as_ref()
-                            .map(|ptr| {
-                                from_utf8(&calculate_key(&buffer, scanner.offsets()))
-                                    .map(|s| ptr.clone_extend(s))
-                            })
-                            .transpose()?,
+                            .zip(key.as_ref())
+                            .map(|(ptr, key)| ptr.clone_extend(&**key)),
                         _ => unreachable!(),
                     },
-                    channel.clone(),
+                    channel,
                     b,
+                    depth + 1,
                 )?;
+                if let (Some(children), Some(subtree)) = (children.as_mut(), subtree) {
+                    match key {
+                        Some(key) => children.push_member(key.into_owned(), subtree),
+                        None => children.push_element(array_count, subtree),
+                    }
+                }
+                if origin.map_or(false, Origin::tracks_offset) {
+                    collapsed.push((buffer.len(), scanner.position() - child))
+                }
+                // Recursive call above eats the corresponding ']' or '}' replace it,
+                // creating an empty array or map
                 buffer.push(b);
-                // Recursive call above eats the corresponding '}' replace it, creating an empty map
-                buffer.push(b'}');
+                buffer.push(if b == b'[' { b']' } else { b'}' });
             }
-            Some(Ok(b @ b']')) | Some(Ok(b @ b'}')) if scanner.outside_quotes() => {
+            Some(b @ b']') | Some(b @ b'}') => {
                 buffer.push(b);
                 break;
             }
-            Some(Ok(b @ b',')) => {
+            Some(b) => {
                 array_count += 1;
                 buffer.push(b)
             }
-            Some(Ok(b)) => buffer.push(b),
-            Some(Err(e)) => return Err(e.into()),
             None => break,
         }
     }
+    let children = children.map(Subtrees::finish);
+    let subtree = match summarize {
+        true => {
+            // Anything between the brackets means there's at least one child
+            let empty = buffer
+                .get(1..buffer.len().saturating_sub(1))
+                .map_or(true, |inner| inner.iter().all(u8::is_ascii_whitespace));
+            let size = container_size(array_count, empty);
+            Some(Subtree::of(opts, size, &buffer, children.as_ref())?)
+        }
+        false => None,
+    };
 
-    channel
-        .send((ident.map(|i| i.into()), jptr, Some(buffer)))
-        .context(Context::udcc())?;
+    channel.send((
+        ident.map(|i| i.into()),
+        jptr,
+        Some(buffer),
+        origin.map(|o| o.at(start, collapsed)),
+        children,
+    ))?;
+    // Each subtree at the split depth ends its batch, and so
+    // is built by the pool separately from its siblings
+    if opts.split_depth() == Some(depth) {
+        channel.flush()?;
+    }
 
-    drop(channel);
-    Ok(())
+    Ok(subtree)
 }
 
 /// Sends the entire read stream to the builder
 /// Only called if the doc is not a object or array
-pub fn unwind_single<I>(
+pub fn unwind_single<R>(
     opts: &ProgramArgs,
     ident: Option<usize>,
-    scanner: &mut JsonScan<I>,
+    origin: Option<&Origin>,
+    scanner: &mut JsonScan<R>,
     prefix_byte: u8,
-    channel: SyncSender<ToBuilder>,
+    channel: &mut ToBuilderTx,
 ) -> Result<()>
 where
-    I: Iterator<Item = ioResult<u8>>,
+    R: BufRead,
 {
-    let buffer: Result<Vec<u8>> = [prefix_byte]
-        .iter()
-        .map(|b| Ok(*b))
-        .chain(scanner)
-        .map(|res| res.map_err(|e| e.into()))
-        .collect();
+    let mut buffer = vec![prefix_byte];
+    let start = scanner.position() - 1;
+    scanner.read_to_end(&mut buffer)?;
 
-    channel
-        .send((
-            ident.map(|i| i.into()),
-            PointerKind::new(opts),
-            Some(buffer?),
-        ))
-        .map_err(|_| ErrorKind::ChannelError)
-        .context(Context::udcc())?;
+    channel.send((
+        ident.map(|i| i.into()),
+        PointerKind::new(opts),
+        Some(buffer),
+        origin.map(|o| o.at(start, Vec::new())),
+        None,
+    ))?;
+
+    Ok(())
+}
+
+/// Walks a JSON value down the given path, unwinding each element
+/// of the array found there as a separate doc. If count is Some, elements
+/// are identified by their (1 based) position, counted across the whole
+/// input. Otherwise, e.g in line delimited inputs, they keep the ident of
+/// their doc (its line), and their pointers start at their (0 based)
+/// position in the array instead, as they would without splitting.
+/// Any value not on the path is skipped
+#[allow(clippy::too_many_arguments)]
+pub fn unwind_split<R>(
+    opts: &ProgramArgs,
+    ident: Option<usize>,
+    origin: Option<&Origin>,
+    scanner: &mut JsonScan<R>,
+    path: &[String],
+    channel: &mut ToBuilderTx,
+    prefix_byte: u8,
+    mut count: Option<&mut usize>,
+) -> Result<()>
+where
+    R: BufRead,
+{
+    let close = match prefix_byte {
+        b'[' => b']',
+        b'{' => b'}',
+        b => {
+            debug!("Skipping a value outside of the split array...");
+            skip_value(scanner, b)?;
+            return Ok(());
+        }
+    };
+    let (segment, rest) = match path.split_first() {
+        Some((segment, rest)) => (Some(segment), rest),
+        None if prefix_byte == b'[' => (None, path),
+        None => {
+            warn!("Split pointer does not point to an array, skipping...");
+            skip_value(scanner, prefix_byte)?;
+            return Ok(());
+        }
+    };
+
+    let mut index = 0usize;
+    let mut next = scanner.next_token()?;
+    loop {
+        match next {
+            None => break,
+            Some(b) if b == close => break,
+            Some(b',') => {
+                index += 1;
+                next = scanner.next_token()?;
+            }
+            // The element we're searching for
+            Some(b) if segment.is_none() => {
+                let (ident, jptr) = match count.as_deref_mut() {
+                    Some(count) => {
+                        *count += 1;
+                        (ident.map(|_| *count), PointerKind::new(opts))
+                    }
+                    None => (ident, PointerKind::new(opts).map(|p| p.clone_extend(index))),
+                };
+                next = match b {
+                    b'[' | b'{' => {
+                        unwind_recursive(opts, ident, origin, scanner, jptr, channel, b, 0)?;
+                        scanner.next_token()?
+                    }
+                    b => {
+                        let start = scanner.position() - 1;
+                        let (buffer, terminator) = read_scalar(scanner, b)?;
+                        channel.send((
+                            ident.map(|i| i.into()),
+                            jptr,
+                            Some(buffer),
+                            origin.map(|o| o.at(start, Vec::new())),
+                            None,
+                        ))?;
+                        terminator_or_next(scanner, terminator)?
+                    }
+                }
+            }
+            // An object member, check its key against the path
+            Some(b'"') if close == b'}' => {
+                let (key, _) = read_scalar(scanner, b'"')?;
+                let key = decode_key(&key[1..key.len() - 1])?;
+                let matches = segment.map_or(false, |s| key == s.as_str());
+                next = match (scanner.next_token()?, scanner.next_token()?) {
+                    (Some(b':'), Some(b)) if matches => {
+                        let count = count.as_deref_mut();
+                        unwind_split(opts, ident, origin, scanner, rest, channel, b, count)?;
+                        scanner.next_token()?
+                    }
+                    (Some(b':'), Some(b)) => skip_value(scanner, b)?,
+                    (_, b) => b,
+                }
+            }
+            // An array element, check its index against the path
+            Some(b) => {
+                next = if segment.map_or(false, |s| *s == index.to_string()) {
+                    let count = count.as_deref_mut();
+                    unwind_split(opts, ident, origin, scanner, rest, channel, b, count)?;
+                    scanner.next_token()?
+                } else {
+                    skip_value(scanner, b)?
+                }
+            }
+        }
+    }
 
     Ok(())
 }
 
-/// Helper function for finding the key
-/// of the JSON object being unwound
-fn calculate_key(buffer: &[u8], offsets: (usize, usize)) -> Vec<u8> {
-    let (in_quotes, out_quotes) = offsets;
-    let mut key: Vec<_> = buffer
+/// Reads a string, number or literal, returning it and
+/// the byte that terminated it, if it was consumed
+fn read_scalar<R>(scanner: &mut JsonScan<R>, prefix_byte: u8) -> Result<(Vec<u8>, Option<u8>)>
+where
+    R: BufRead,
+{
+    let mut buffer = vec![prefix_byte];
+    loop {
+        match scanner.next_byte()? {
+            Some(b @ b'"') if prefix_byte == b'"' && scanner.outside_quotes() => {
+                buffer.push(b);
+                return Ok((buffer, None));
+            }
+            // '[', '{' and '"' can only follow in concatenated docs, e.g '1{}'
+            Some(b)
+                if prefix_byte != b'"' && (b.is_ascii_whitespace() || b",]}[{\"".contains(&b)) =>
+            {
+                return Ok((buffer, Some(b)))
+            }
+            Some(b) => buffer.push(b),
+            None => return Ok((buffer, None)),
+        }
+    }
+}
+
+/// Consumes a JSON value, returning the next token after it
+fn skip_value<R>(scanner: &mut JsonScan<R>, prefix_byte: u8) -> Result<Option<u8>>
+where
+    R: BufRead,
+{
+    match prefix_byte {
+        b'[' | b'{' => {
+            let _ = consume_container(scanner, |_| ())?;
+            scanner.next_token().map_err(|e| e.into())
+        }
+        b => {
+            let (_, terminator) = read_scalar(scanner, b)?;
+            terminator_or_next(scanner, terminator)
+        }
+    }
+}
+
+/// Consumes the rest of an object or array, handing each slice of it to f.
+/// Returns the number of its elements (or members)
+fn consume_container<R, F>(scanner: &mut JsonScan<R>, mut f: F) -> Result<usize>
+where
+    R: BufRead,
+    F: FnMut(&[u8]),
+{
+    let mut depth = 1usize;
+    let (mut commas, mut empty) = (0usize, true);
+    while depth > 0 {
+        let found = scanner.read_until_structural(|slice| {
+            empty = empty && slice.iter().all(u8::is_ascii_whitespace);
+            f(slice)
+        })?;
+        match found {
+            Some(b) => {
+                match b {
+                    b'[' | b'{' => {
+                        empty = false;
+                        depth += 1
+                    }
+                    b']' | b'}' => depth -= 1,
+                    b',' if depth == 1 => commas += 1,
+                    _ => {}
+                }
+                f(&[b])
+            }
+            None => break,
+        }
+    }
+
+    Ok(container_size(commas, empty))
+}
+
+/// The number of children of a container, from the
+/// commas separating them and whether it is empty
+fn container_size(commas: usize, empty: bool) -> usize {
+    match empty {
+        true => 0,
+        false => commas + 1,
+    }
+}
+
+/// Helper for resuming after a scalar, whose terminating byte
+/// may have been whitespace
+fn terminator_or_next<R>(scanner: &mut JsonScan<R>, terminator: Option<u8>) -> Result<Option<u8>>
+where
+    R: BufRead,
+{
+    match terminator {
+        Some(b) if !b.is_ascii_whitespace() => Ok(Some(b)),
+        _ => scanner.next_token().map_err(|e| e.into()),
+    }
+}
+
+/// Helper function for finding the key of the JSON object
+/// being unwound, i.e the last string before the trailing ':'
+fn calculate_key(buffer: &[u8]) -> &[u8] {
+    let end = buffer
         .iter()
+        .rposition(|b| !b.is_ascii_whitespace() && *b != b':')
+        .unwrap_or(0);
+    // The opening quote is the first one (searching backwards) that
+    // isn't escaped, i.e not preceded by an odd number of '\'
+    let start = (0..end)
         .rev()
-        .skip(out_quotes)
-        .take(in_quotes)
-        .copied()
-        .collect();
-    key.reverse();
+        .find(|&i| {
+            buffer[i] == b'"'
+                && buffer[..i]
+                    .iter()
+                    .rev()
+                    .take_while(|b| **b == b'\\')
+                    .count()
+                    % 2
+                    == 0
+        })
+        .map_or(end, |i| i + 1);
+    let key = &buffer[start..end];
 
-    trace!("KEY: {:?}", from_utf8(&key));
+    trace!("KEY: {:?}", from_utf8(key));
 
     key
 }
 
+/// Decodes any JSON escapes in a raw key (sans quotes), so that keys
+/// taken from the stream match those of deserialized objects
+fn decode_key(raw: &[u8]) -> Result<Cow<'_, str>> {
+    match memchr(b'\\', raw) {
+        None => Ok(Cow::Borrowed(from_utf8(raw)?)),
+        Some(_) => {
+            let mut quoted = Vec::with_capacity(raw.len() + 2);
+            quoted.push(b'"');
+            quoted.extend_from_slice(raw);
+            quoted.push(b'"');
+            Ok(Cow::Owned(serde_json::from_slice(&quoted)?))
+        }
+    }
+}
+
 /// Specialized writer function for Output using Builder
-pub fn write_formatted_output<B, W>(w: &mut W, blocks: B, blueprint: &[Field]) -> Result<()>
+pub fn write_formatted_output<B, W>(w: &mut W, blocks: B, blueprint: &[Column]) -> Result<()>
 where
     B: Builder<Field>,
     W: ioWrite,
     ErrorKind: From<<B as Builder<Field>>::Error>,
 {
+    let guard = blocks.guard()?.to_string();
+    // Each cell is formatted here first, so that guards inside it can be found
+    let mut cell = String::new();
     let iter = blueprint.iter().identify_first_last();
-    for (_, last, field) in iter {
-        write!(
-            w,
-            "{}{}{}",
-            blocks.guard()?,
-            blocks.build_with(*field)?,
-            blocks.guard()?
-        )?;
+    for (_, last, column) in iter {
+        cell.clear();
+        match column {
+            Column::Field { field, .. } => write!(cell, "{}", blocks.build_with(*field)?)?,
+            Column::Literal { value, .. } => cell.push_str(value),
+        }
+        write_guarded(w, &guard, &cell)?;
         if !last {
             write!(w, "{}", blocks.delimiter()?)?;
         }
@@ -383,6 +768,46 @@ where
     Ok(())
 }
 
+/// Writes a line naming each column, formatted the same as the rows below it
+pub fn write_header<W>(
+    w: &mut W,
+    columns: &[Column],
+    guard: &Guard,
+    delimiter: &Delimiter,
+) -> Result<()>
+where
+    W: ioWrite,
+{
+    let guard = guard.to_string();
+    let iter = columns.iter().identify_first_last();
+    for (_, last, column) in iter {
+        write_guarded(w, &guard, column.header())?;
+        if !last {
+            write!(w, "{}", delimiter)?;
+        }
+    }
+    writeln!(w)?;
+
+    Ok(())
+}
+
+/// Writes a cell between guards, doubling any guard inside it as CSV
+/// does, e.g '"a"' becomes '"""a"""'. Without a guard it is written as is
+fn write_guarded<W>(w: &mut W, guard: &str, cell: &str) -> Result<()>
+where
+    W: ioWrite,
+{
+    match guard {
+        "" => write!(w, "{}", cell)?,
+        guard if cell.contains(guard) => {
+            write!(w, "{0}{1}{0}", guard, cell.replace(guard, &guard.repeat(2)))?
+        }
+        guard => write!(w, "{0}{1}{0}", guard, cell)?,
+    }
+
+    Ok(())
+}
+
 /// Helper function for early parse skipping, based on input ident
 pub fn check_index(regex: Option<&RegexOptions>, ident: Option<usize>) -> bool {
     ident.map_or(true, |i| match regex {
@@ -407,3 +832,179 @@ where
 {
     f(&CLI, arg)
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::cli::generate_cli, clap::AppSettings, serde_json::Map,
+        std::sync::mpsc::sync_channel,
+    };
+
+    /// Unwinds a concatenated stream, returning each packet's ident and data
+    fn concatenated(data: &str) -> Vec<(usize, String)> {
+        let store = generate_cli()
+            .setting(AppSettings::NoBinaryName)
+            .get_matches_from(&["-f", "ident.value"]);
+        let opts = ProgramArgs::from_store(&store);
+        let (tx, rx) = sync_channel(64);
+        let mut channel = BatchSender::new(tx, 4);
+        unwind_concatenated(&opts, Some(1), None, data.as_bytes(), None, &mut channel).unwrap();
+        drop(channel);
+
+        rx.iter()
+            .flatten()
+            .map(|(ident, _, data, _, _)| {
+                let ident = *ident.unwrap().as_ref();
+                (ident, String::from_utf8(data.unwrap()).unwrap())
+            })
+            .collect()
+    }
+
+    /// Decodes the key of a single member object's buffer,
+    /// and the same key as deserialized by serde
+    fn both_keys(buffer: &str) -> (String, String) {
+        let decoded = decode_key(calculate_key(buffer.as_bytes()))
+            .unwrap()
+            .into_owned();
+        let map: Map<String, serde_json::Value> =
+            serde_json::from_str(&format!("{}null}}", buffer)).unwrap();
+
+        (decoded, map.keys().next().unwrap().clone())
+    }
+
+    #[test]
+    fn decode_plain_key() {
+        let (decoded, expected) = both_keys(r#"{ "b" :"#);
+        assert_eq!(decoded, "b");
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn decode_unicode_key() {
+        for buffer in &[r#"{"caf\u00e9":"#, r#"{"café":"#] {
+            let (decoded, expected) = both_keys(buffer);
+            assert_eq!(decoded, "café");
+            assert_eq!(decoded, expected);
+        }
+        let (decoded, expected) = both_keys(r#"{"\ud83d\ude00":"#);
+        assert_eq!(decoded, "\u{1F600}");
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn concatenated_docs_and_scalars() {
+        let packets = concatenated(r#"{"a":1}{"b":[2]} 3 "x"-4.5[{"c":"}"}]true null"#);
+
+        assert_eq!(
+            packets,
+            vec![
+                (1, r#"{"a":1}"#.to_string()),
+                (2, "[2]".to_string()),
+                (2, r#"{"b":[]}"#.to_string()),
+                (3, "3".to_string()),
+                (4, r#""x""#.to_string()),
+                (5, "-4.5".to_string()),
+                (6, r#"{"c":"}"}"#.to_string()),
+                (6, "[{}]".to_string()),
+                (7, "true".to_string()),
+                (8, "null".to_string()),
+            ]
+        );
+    }
+
+    /// Splits each line's array, returning each packet's ident and pointer
+    fn split_lines(lines: &[&str], mut count: Option<&mut usize>) -> Vec<(usize, String)> {
+        let store = generate_cli()
+            .setting(AppSettings::NoBinaryName)
+            .get_matches_from(&["-f", "ident.jptr.index"]);
+        let opts = ProgramArgs::from_store(&store);
+        let (tx, rx) = sync_channel(64);
+        let mut channel = BatchSender::new(tx, 4);
+        for (line, data) in lines.iter().enumerate() {
+            let (ident, source, count) =
+                (Some(line + 1), Some(data.as_bytes()), count.as_deref_mut());
+            unwind_json(&opts, ident, None, source, Some(&[]), count, &mut channel).unwrap();
+        }
+        drop(channel);
+
+        rx.iter()
+            .flatten()
+            .map(|(ident, ptr, _, _, _)| (*ident.unwrap().as_ref(), ptr.unwrap().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn split_elements_keep_line_ident() {
+        assert_eq!(
+            split_lines(&[r#"[{"a":1},2]"#, "[3]"], None),
+            vec![
+                (1, "/0".to_string()),
+                (1, "/1".to_string()),
+                (2, "/0".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_elements_counted() {
+        assert_eq!(
+            split_lines(&[r#"[{"a":1},2]"#], Some(&mut 0)),
+            vec![(1, "".to_string()), (2, "".to_string())]
+        );
+    }
+
+    #[test]
+    fn header_doubles_guard() -> Result<()> {
+        let mut buffer = Vec::new();
+        let columns = [
+            Column::Field {
+                field: Field::Pointer,
+                alias: Some("the \"path\"".into()),
+            },
+            Column::Literal {
+                value: "\"".into(),
+                alias: None,
+            },
+        ];
+        write_header(
+            &mut buffer,
+            &columns,
+            &Guard::from("\""),
+            &Delimiter::from(","),
+        )?;
+
+        assert_eq!(
+            String::from_utf8_lossy(&buffer),
+            "\"the \"\"path\"\"\",\"\"\"\"\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn consume_container_counts_children() {
+        for (json, size) in &[
+            ("[]", 0),
+            ("{ \n }", 0),
+            ("[1]", 1),
+            (r#"{"a": [1, 2], "b,": {"c": 3}}"#, 2),
+            (r#"[[], {}, "]", ","]"#, 4),
+        ] {
+            let mut scanner = JsonScan::new(json.as_bytes());
+            scanner.next_byte().unwrap();
+            let mut consumed = Vec::new();
+            let counted = consume_container(&mut scanner, |s| consumed.extend_from_slice(s));
+
+            assert_eq!(counted.unwrap(), *size, "{}", json);
+            assert_eq!(&consumed[..], &json.as_bytes()[1..]);
+        }
+    }
+
+    #[test]
+    fn decode_escaped_quote_key() {
+        for (buffer, key) in &[(r#"{"a\"b\\":"#, r#"a"b\"#), (r#"{"\"":"#, r#"""#)] {
+            let (decoded, expected) = both_keys(buffer);
+            assert_eq!(decoded, *key);
+            assert_eq!(decoded, expected);
+        }
+    }
+}
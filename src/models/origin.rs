@@ -0,0 +1,298 @@
+use {
+    crate::models::{decode_key, error::Result},
+    std::sync::Arc,
+};
+
+/// Where a packet was found in its input, carried alongside it
+/// from the reader to the builder
+#[derive(Debug, Clone)]
+pub struct Origin {
+    source: Arc<str>,
+    line: Option<usize>,
+    /// Input offset of the packet's first byte, None
+    /// if offsets aren't tracked
+    offset: Option<u64>,
+    /// Child containers unwound as packets of their own, each left
+    /// behind in this packet as an empty '[]' or '{}'. Stored as
+    /// (position in the packet, length in the input)
+    collapsed: Vec<(usize, u64)>,
+}
+
+impl Origin {
+    /// The origin of a whole input (or record), tracking
+    /// offsets from 'offset' if it is Some
+    pub fn new(source: Arc<str>, line: Option<usize>, offset: Option<u64>) -> Self {
+        Origin {
+            source,
+            line,
+            offset,
+            collapsed: Vec::new(),
+        }
+    }
+
+    /// The origin of a packet starting 'start' bytes into this one
+    pub fn at(&self, start: u64, collapsed: Vec<(usize, u64)>) -> Self {
+        Origin {
+            source: Arc::clone(&self.source),
+            line: self.line,
+            offset: self.offset.map(|offset| offset + start),
+            collapsed,
+        }
+    }
+
+    pub fn source(&self) -> &Arc<str> {
+        &self.source
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    pub fn tracks_offset(&self) -> bool {
+        self.offset.is_some()
+    }
+
+    /// Finds the input span of every value in this origin's packet,
+    /// None if offsets aren't tracked
+    pub fn index(&self, packet: &[u8]) -> Result<Option<Span>> {
+        match self.offset {
+            Some(offset) => {
+                let mut indexer = Indexer {
+                    packet,
+                    pos: 0,
+                    base: offset,
+                    collapsed: &self.collapsed,
+                    shift: 0,
+                };
+                indexer.value().map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// The bytes a JSON value occupies in its input,
+/// and those of its children (if any)
+#[derive(Debug, PartialEq)]
+pub struct Span {
+    offset: u64,
+    len: u64,
+    children: Children,
+}
+
+#[derive(Debug, PartialEq)]
+enum Children {
+    None,
+    Array(Vec<Span>),
+    /// Sorted by key, keeping the source order of duplicates
+    Object(Vec<(String, Span)>),
+}
+
+impl Span {
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn element(&self, index: usize) -> Option<&Span> {
+        match self.children {
+            Children::Array(ref elements) => elements.get(index),
+            _ => None,
+        }
+    }
+
+    /// The span of the member with the given key, if a key
+    /// is repeated the last one is used, as serde does
+    pub fn member(&self, key: &str) -> Option<&Span> {
+        match self.children {
+            Children::Object(ref members) => {
+                match members.partition_point(|(k, _)| k.as_str() <= key) {
+                    0 => None,
+                    i => Some(&members[i - 1])
+                        .filter(|(k, _)| k == key)
+                        .map(|(_, s)| s),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Walks a packet, which must be valid JSON, recording the span
+/// of each value. Positions are mapped back to the input by adding
+/// the lengths of any collapsed containers already passed
+struct Indexer<'p> {
+    packet: &'p [u8],
+    pos: usize,
+    base: u64,
+    collapsed: &'p [(usize, u64)],
+    shift: u64,
+}
+
+impl<'p> Indexer<'p> {
+    fn peek(&self) -> Option<u8> {
+        self.packet.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, |b| b.is_ascii_whitespace()) {
+            self.pos += 1
+        }
+    }
+
+    /// The input offset of the current position
+    fn offset(&self) -> u64 {
+        self.base + self.pos as u64 + self.shift
+    }
+
+    fn value(&mut self) -> Result<Span> {
+        self.skip_whitespace();
+        let start = self.offset();
+        let children = match self.peek() {
+            Some(b'[') | Some(b'{') if self.collapsed.first().map(|c| c.0) == Some(self.pos) => {
+                let len = self.collapsed[0].1;
+                self.collapsed = &self.collapsed[1..];
+                self.pos += 2;
+                self.shift += len - 2;
+                return Ok(Span {
+                    offset: start,
+                    len,
+                    children: Children::None,
+                });
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut elements = Vec::new();
+                while self.next_item(b']') {
+                    elements.push(self.value()?);
+                }
+                Children::Array(elements)
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                while self.next_item(b'}') {
+                    let key = {
+                        let raw = self.string();
+                        decode_key(&raw[1..raw.len() - 1])?.into_owned()
+                    };
+                    self.skip_whitespace();
+                    // The ':'
+                    self.pos += 1;
+                    members.push((key, self.value()?));
+                }
+                // Stable, so duplicate keys keep their order
+                members.sort_by(|a, b| a.0.cmp(&b.0));
+                Children::Object(members)
+            }
+            Some(b'"') => {
+                self.string();
+                Children::None
+            }
+            _ => {
+                while self
+                    .peek()
+                    .map_or(false, |b| !b.is_ascii_whitespace() && !b",]}".contains(&b))
+                {
+                    self.pos += 1
+                }
+                Children::None
+            }
+        };
+
+        Ok(Span {
+            offset: start,
+            len: self.offset() - start,
+            children,
+        })
+    }
+
+    /// Moves to the next item of an array or object, returning
+    /// false (and consuming it) once the closing byte is reached
+    fn next_item(&mut self, close: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+        match self.peek() {
+            Some(b) if b == close => {
+                self.pos += 1;
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    /// Consumes a string, returning it with its quotes
+    fn string(&mut self) -> &'p [u8] {
+        let start = self.pos;
+        self.pos += 1;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                b'\\' => self.pos += 1,
+                b'"' => break,
+                _ => {}
+            }
+        }
+        self.pos = self.pos.min(self.packet.len());
+
+        &self.packet[start..self.pos]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(packet: &str, offset: u64, collapsed: Vec<(usize, u64)>) -> Span {
+        Origin::new(Arc::from("-"), None, Some(0))
+            .at(offset, collapsed)
+            .index(packet.as_bytes())
+            .unwrap()
+            .unwrap()
+    }
+
+    fn range(span: &Span) -> (u64, u64) {
+        (span.offset(), span.len())
+    }
+
+    #[test]
+    fn spans_of_nested_values() {
+        let span = index(r#" {"b": [1, "x\"y"], "aA" : null} "#, 10, Vec::new());
+
+        assert_eq!(range(&span), (11, 31));
+        let b = span.member("b").unwrap();
+        assert_eq!(range(b), (17, 11));
+        assert_eq!(range(b.element(0).unwrap()), (18, 1));
+        assert_eq!(range(b.element(1).unwrap()), (21, 6));
+        assert_eq!(range(span.member("aA").unwrap()), (37, 4));
+        assert!(span.member("a").is_none());
+        assert!(b.element(2).is_none());
+    }
+
+    #[test]
+    fn spans_past_collapsed_containers() {
+        // Unwound from: [{"a": 1}, [2, 3], 4]
+        let span = index("[{}, [], 4]", 0, vec![(1, 8), (5, 6)]);
+
+        assert_eq!(range(&span), (0, 21));
+        assert_eq!(range(span.element(0).unwrap()), (1, 8));
+        assert_eq!(range(span.element(1).unwrap()), (11, 6));
+        assert_eq!(range(span.element(2).unwrap()), (19, 1));
+        assert!(span.element(0).unwrap().member("a").is_none());
+    }
+
+    #[test]
+    fn spans_of_duplicate_keys() {
+        let span = index(r#"{"a": 1, "b": 2, "a": 3}"#, 0, Vec::new());
+
+        assert_eq!(range(span.member("a").unwrap()), (22, 1));
+        assert_eq!(range(span.member("b").unwrap()), (14, 1));
+    }
+}
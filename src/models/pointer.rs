@@ -1,99 +1,371 @@
 use {
-    crate::{
-        cli::ProgramArgs,
-        models::{
-            error::{ErrorKind, Result},
-            field::Field,
-        },
+    crate::{cli::ProgramArgs, models::field::Field},
+    std::{
+        fmt::{self, Display, Write},
+        sync::{Arc, OnceLock},
     },
-    std::{fmt::Display, sync::Arc},
 };
 
 pub trait Pointer<T: Into<PointerKind> = PointerKind> {
     fn clone_extend<D: Display + Into<PointerParts>>(&self, other: D) -> T;
 
-    fn as_complete(&self) -> String;
+    /// Writes the pointer in the given path syntax
+    fn fmt_path(&self, f: &mut fmt::Formatter, syntax: PathSyntax) -> fmt::Result;
+}
+
+/// How json pointers are written
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PointerStyle {
+    /// As is, i.e '/a~1b/0'
+    #[default]
+    Plain,
+    /// As a URI fragment, i.e '#/a~1b/0' with any
+    /// bytes not allowed in a fragment percent encoded
+    Fragment,
+}
 
-    fn as_parts(&self) -> Result<&Vec<PointerParts>>;
+/// The path languages a pointer can be written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSyntax {
+    /// '/a/b c/0'
+    Pointer,
+    /// 'a."b c"[0]'
+    Jmes,
+    /// "$.a['b c'][0]"
+    JsonPath,
+    /// '.a["b c"][0]'
+    Jq,
 }
 
+impl PathSyntax {
+    const COUNT: usize = 4;
+
+    /// What every path starts with
+    fn prefix(self, style: PointerStyle) -> &'static str {
+        match (self, style) {
+            (Self::Pointer, PointerStyle::Fragment) => "#",
+            (Self::JsonPath, _) => "$",
+            _ => "",
+        }
+    }
+
+    /// The path of the document itself
+    fn root(self, style: PointerStyle) -> &'static str {
+        match self {
+            Self::Jmes => "@",
+            Self::Jq => ".",
+            _ => self.prefix(style),
+        }
+    }
+}
+
+/// A persistent JSON pointer, each part holding a link to its parent.
+/// Extending a pointer is O(1) and shares the prefix with every other
+/// pointer extended from the same parent. Nothing is assembled until
+/// the pointer is displayed, at which point each parent assembles
+/// (and keeps) its own prefix, so siblings only pay for it once
 #[derive(Debug, Clone)]
-pub enum PointerKind {
-    Simple(String),
-    Complex(Complex),
+pub struct PointerKind {
+    tail: Option<Arc<Link>>,
+    style: PointerStyle,
 }
 
 impl PointerKind {
     pub fn new(opts: &ProgramArgs) -> Option<Self> {
-        match (
-            opts.should_calculate(Field::Pointer),
-            opts.should_calculate(Field::JmesPath),
-        ) {
-            (_, true) => Some(PointerKind::Complex(Complex::new())),
-            (true, false) => Some(PointerKind::Simple(String::new())),
-            (false, false) => None,
+        // Every field derived from the pointer depends on it
+        match opts.should_calculate(Field::Pointer) {
+            false => None,
+            true => Some(PointerKind {
+                tail: None,
+                style: opts.pointer_style(),
+            }),
+        }
+    }
+
+    /// Number of parts in the pointer, 0 being the root
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut link = self.tail.as_ref();
+        while let Some(l) = link {
+            depth += 1;
+            link = l.parent.as_ref();
+        }
+
+        depth
+    }
+
+    /// The pointer's last part, None for the root
+    pub fn last(&self) -> Option<&PointerParts> {
+        self.tail.as_ref().map(|link| &link.part)
+    }
+
+    /// The pointer this one extends, None for the root
+    pub fn parent(&self) -> Option<PointerKind> {
+        self.tail.as_ref().map(|link| PointerKind {
+            tail: link.parent.clone(),
+            style: self.style,
+        })
+    }
+
+    /// Writes a key that sorts pointers part by part, array
+    /// indices numerically, with each pointer before any extending it
+    pub fn write_sort_key(&self, key: &mut Vec<u8>) {
+        if let Some(ref link) = self.tail {
+            link.write_sort_key(key)
         }
     }
 }
 
 impl Pointer for PointerKind {
     fn clone_extend<D: Display + Into<PointerParts>>(&self, other: D) -> PointerKind {
-        match self {
-            Self::Simple(s) => Self::Simple(format!("{}/{}", s, other)),
-            Self::Complex(c) => Self::Complex(c.internal_clone_extend(other)),
+        PointerKind {
+            tail: Some(Arc::new(Link {
+                parent: self.tail.clone(),
+                part: other.into(),
+                memo: OnceLock::new(),
+            })),
+            style: self.style,
         }
     }
 
-    fn as_complete(&self) -> String {
-        match self {
-            Self::Simple(s) => s.clone(),
-            Self::Complex(c) => format!("{}", c),
+    fn fmt_path(&self, f: &mut fmt::Formatter, syntax: PathSyntax) -> fmt::Result {
+        match self.tail {
+            Some(ref link) => {
+                match link.parent {
+                    Some(ref parent) => f.write_str(parent.path(syntax, self.style))?,
+                    None => f.write_str(syntax.prefix(self.style))?,
+                }
+                link.part
+                    .write_path(f, syntax, self.style, link.parent.is_none())
+            }
+            None => f.write_str(syntax.root(self.style)),
         }
     }
+}
 
-    fn as_parts(&self) -> Result<&Vec<PointerParts>> {
-        match self {
-            Self::Simple(_) => Err(ErrorKind::Message(format!(
-                "Logic error: called 'as_parts' on Simple, this is a bug"
-            ))
-            .into()),
-            Self::Complex(c) => Ok(&c.inner),
+impl Display for PointerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_path(f, PathSyntax::Pointer)
+    }
+}
+
+#[derive(Debug)]
+struct Link {
+    parent: Option<Arc<Link>>,
+    part: PointerParts,
+    /// Only parents are ever asked for their prefix, so
+    /// this is boxed to keep the (many more) leaves small
+    memo: OnceLock<Box<Memo>>,
+}
+
+/// One assembled path per syntax
+#[derive(Debug, Default)]
+struct Memo([OnceLock<Box<str>>; PathSyntax::COUNT]);
+
+impl Link {
+    fn write_sort_key(&self, key: &mut Vec<u8>) {
+        if let Some(ref parent) = self.parent {
+            parent.write_sort_key(key)
+        }
+        match self.part {
+            PointerParts::Array(u) => {
+                key.push(1);
+                key.extend_from_slice(&(u as u64).to_be_bytes())
+            }
+            // Keys are terminated by 0 0, so any 0 inside is escaped as 0 255
+            PointerParts::Object(ref s) => {
+                key.push(2);
+                for &b in s.as_bytes() {
+                    match b {
+                        0 => key.extend_from_slice(&[0, 255]),
+                        b => key.push(b),
+                    }
+                }
+                key.extend_from_slice(&[0, 0])
+            }
         }
     }
+
+    /// This link's path, assembled on first use.
+    /// Every pointer sharing a link shares its style
+    fn path(&self, syntax: PathSyntax, style: PointerStyle) -> &str {
+        let memo = self.memo.get_or_init(Default::default);
+        memo.0[syntax as usize].get_or_init(|| {
+            let mut buffer = String::from(match self.parent {
+                Some(ref parent) => parent.path(syntax, style),
+                None => syntax.prefix(style),
+            });
+            // Writing to a String can't fail
+            let _ = self
+                .part
+                .write_path(&mut buffer, syntax, style, self.parent.is_none());
+            buffer.into_boxed_str()
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct Complex {
-    inner: Vec<PointerParts>,
+pub enum PointerParts {
+    Object(Arc<str>),
+    Array(usize),
 }
 
-impl Complex {
-    fn new() -> Self {
-        Complex { inner: Vec::new() }
+impl PointerParts {
+    /// Writes this part in the given syntax, 'first' being
+    /// whether it is the first part after the prefix
+    fn write_path<W: Write>(
+        &self,
+        w: &mut W,
+        syntax: PathSyntax,
+        style: PointerStyle,
+        first: bool,
+    ) -> fmt::Result {
+        match (syntax, self) {
+            (PathSyntax::Pointer, Self::Object(s)) => {
+                w.write_char('/')?;
+                write_escaped(w, s, style)
+            }
+            (PathSyntax::Pointer, Self::Array(u)) => write!(w, "/{}", u),
+            (PathSyntax::Jmes, Self::Object(s)) => {
+                if !first {
+                    w.write_char('.')?
+                }
+                match is_identifier(s) {
+                    true => w.write_str(s),
+                    false => write_quoted(w, s, '"'),
+                }
+            }
+            (PathSyntax::JsonPath, Self::Object(s)) if is_identifier(s) => write!(w, ".{}", s),
+            (PathSyntax::JsonPath, Self::Object(s)) => {
+                w.write_char('[')?;
+                write_quoted(w, s, '\'')?;
+                w.write_char(']')
+            }
+            (PathSyntax::Jq, Self::Object(s)) if is_identifier(s) => write!(w, ".{}", s),
+            (PathSyntax::Jq, Self::Object(s)) => {
+                w.write_str(if first { ".[" } else { "[" })?;
+                write_quoted(w, s, '"')?;
+                w.write_char(']')
+            }
+            (PathSyntax::Jq, Self::Array(u)) if first => write!(w, ".[{}]", u),
+            (_, Self::Array(u)) => write!(w, "[{}]", u),
+        }
     }
+}
 
-    fn internal_clone_extend<P: Into<PointerParts>>(&self, item: P) -> Complex {
-        let mut new = self.clone();
-        new.inner
-            .extend_from_slice(&[PointerParts::Slash, item.into()]);
-        new
+/// Whether a key can be written bare, i.e [A-Za-z_][A-Za-z0-9_]*
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
     }
 }
 
-impl Display for Complex {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for item in self.inner.iter() {
-            write!(f, "{}", item)?
+/// Writes a key between 'quote's, escaping as a JSON string would
+fn write_quoted<W: Write>(w: &mut W, key: &str, quote: char) -> fmt::Result {
+    w.write_char(quote)?;
+    for c in key.chars() {
+        match c {
+            '\\' => w.write_str("\\\\")?,
+            c if c == quote => {
+                w.write_char('\\')?;
+                w.write_char(c)?
+            }
+            '\n' => w.write_str("\\n")?,
+            '\r' => w.write_str("\\r")?,
+            '\t' => w.write_str("\\t")?,
+            c if c.is_control() => write!(w, "\\u{:04x}", c as u32)?,
+            c => w.write_char(c)?,
         }
-        Ok(())
     }
+
+    w.write_char(quote)
 }
 
-#[derive(Debug, Clone)]
-pub enum PointerParts {
-    Slash,
-    Object(Arc<str>),
-    Array(usize),
+/// Writes a key as a pointer reference token, escaping '~' and '/'
+/// (RFC 6901) and, for fragments, percent encoding (RFC 3986)
+fn write_escaped<W: Write>(w: &mut W, key: &str, style: PointerStyle) -> fmt::Result {
+    let mut start = 0;
+    for (i, c) in key.char_indices() {
+        let plain = match (c, style) {
+            ('~', _) | ('/', _) => false,
+            (c, PointerStyle::Fragment) => is_fragment_char(c),
+            (_, PointerStyle::Plain) => true,
+        };
+        if plain {
+            continue;
+        }
+        w.write_str(&key[start..i])?;
+        match c {
+            '~' => w.write_str("~0")?,
+            '/' => w.write_str("~1")?,
+            c => {
+                for b in c.encode_utf8(&mut [0; 4]).bytes() {
+                    write!(w, "%{:02X}", b)?
+                }
+            }
+        }
+        start = i + c.len_utf8();
+    }
+
+    w.write_str(&key[start..])
+}
+
+/// Characters allowed as is in a URI fragment, besides '/'
+fn is_fragment_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~!$&'()*+,;=:@?".contains(c)
+}
+
+/// Parses a JSON pointer, or its URI fragment form, into its
+/// (unescaped) reference tokens. Returns None if it isn't valid
+pub fn parse_pointer(pointer: &str) -> Option<Vec<String>> {
+    let pointer = match pointer.as_bytes().first() {
+        Some(b'#') => percent_decode(&pointer[1..])?,
+        _ => pointer.to_string(),
+    };
+    match pointer.as_str() {
+        "" => Some(Vec::new()),
+        p if p.starts_with('/') => p[1..].split('/').map(unescape_token).collect(),
+        _ => None,
+    }
+}
+
+/// Reverses the '~0' and '~1' escapes of a reference token
+fn unescape_token(token: &str) -> Option<String> {
+    let mut buffer = String::with_capacity(token.len());
+    let mut iter = token.chars();
+    while let Some(c) = iter.next() {
+        match c {
+            '~' => match iter.next() {
+                Some('0') => buffer.push('~'),
+                Some('1') => buffer.push('/'),
+                _ => return None,
+            },
+            c => buffer.push(c),
+        }
+    }
+
+    Some(buffer)
+}
+
+fn percent_decode(fragment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(fragment.len());
+    let mut iter = fragment.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'%' => {
+                let hex = [iter.next()?, iter.next()?];
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?)
+            }
+            b => bytes.push(b),
+        }
+    }
+
+    String::from_utf8(bytes).ok()
 }
 
 impl From<&str> for PointerParts {
@@ -109,11 +381,173 @@ impl From<usize> for PointerParts {
 }
 
 impl Display for PointerParts {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Slash => write!(f, "/"),
             Self::Array(u) => write!(f, "{}", u),
             Self::Object(s) => write!(f, "{}", s),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Path<'a>(&'a PointerKind, PathSyntax);
+
+    impl Display for Path<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.fmt_path(f, self.1)
+        }
+    }
+
+    fn root(style: PointerStyle) -> PointerKind {
+        PointerKind { tail: None, style }
+    }
+
+    #[test]
+    fn extend_shares_prefix() {
+        let root = root(PointerStyle::Plain);
+        let parent = root.clone_extend("a").clone_extend(0);
+        let left = parent.clone_extend("b");
+        let right = parent.clone_extend(1);
+
+        assert_eq!(root.to_string(), "");
+        assert_eq!(left.to_string(), "/a/0/b");
+        assert_eq!(right.to_string(), "/a/0/1");
+        assert_eq!(parent.to_string(), "/a/0");
+        // Held by parent, left and right
+        assert_eq!(Arc::strong_count(parent.tail.as_ref().unwrap()), 3);
+    }
+
+    #[test]
+    fn jmes_from_pointer() {
+        let jmes = |p: &PointerKind| Path(p, PathSyntax::Jmes).to_string();
+        let root = root(PointerStyle::Plain);
+        let parent = root.clone_extend(0).clone_extend("a");
+
+        assert_eq!(jmes(&root), "@");
+        assert_eq!(jmes(&parent.clone_extend("b")), "[0].a.b");
+        assert_eq!(jmes(&root.clone_extend("a").clone_extend(2)), "a[2]");
+        assert_eq!(
+            jmes(&root.clone_extend("b c").clone_extend("d\"\n")),
+            r#""b c"."d\"\n""#
+        );
+        assert_eq!(
+            jmes(&root.clone_extend("1a").clone_extend("")),
+            r#""1a"."""#
+        );
+    }
+
+    #[test]
+    fn jsonpath_from_pointer() {
+        let jsonpath = |p: &PointerKind| Path(p, PathSyntax::JsonPath).to_string();
+        let root = root(PointerStyle::Fragment);
+        let parent = root.clone_extend("a").clone_extend("b c");
+
+        assert_eq!(jsonpath(&root), "$");
+        assert_eq!(jsonpath(&parent.clone_extend(0)), "$.a['b c'][0]");
+        assert_eq!(jsonpath(&root.clone_extend(1)), "$[1]");
+        assert_eq!(jsonpath(&root.clone_extend("it's")), r"$['it\'s']");
+    }
+
+    #[test]
+    fn jq_from_pointer() {
+        let jq = |p: &PointerKind| Path(p, PathSyntax::Jq).to_string();
+        let root = root(PointerStyle::Plain);
+
+        assert_eq!(jq(&root), ".");
+        assert_eq!(
+            jq(&root.clone_extend("a").clone_extend("b c").clone_extend(0)),
+            r#".a["b c"][0]"#
+        );
+        assert_eq!(jq(&root.clone_extend(1).clone_extend("_x")), ".[1]._x");
+        assert_eq!(jq(&root.clone_extend("a/b")), r#".["a/b"]"#);
+    }
+
+    #[test]
+    fn depth_last_and_parent() {
+        let root = root(PointerStyle::Fragment);
+        let ptr = root.clone_extend("a b").clone_extend(3);
+
+        assert_eq!(root.depth(), 0);
+        assert_eq!(ptr.depth(), 2);
+        assert!(root.last().is_none());
+        assert_eq!(ptr.last().map(|p| p.to_string()), Some(String::from("3")));
+        assert!(root.parent().is_none());
+        assert_eq!(
+            ptr.parent().map(|p| p.to_string()),
+            Some(String::from("#/a%20b"))
+        );
+        assert_eq!(
+            ptr.parent().and_then(|p| p.parent()).map(|p| p.to_string()),
+            Some(String::from("#"))
+        );
+    }
+
+    #[test]
+    fn escape_keys() {
+        let plain = root(PointerStyle::Plain).clone_extend("application/json");
+        let fragment = root(PointerStyle::Fragment).clone_extend("a b");
+
+        assert_eq!(
+            plain.clone_extend("~x").to_string(),
+            "/application~1json/~0x"
+        );
+        assert_eq!(fragment.to_string(), "#/a%20b");
+        assert_eq!(
+            fragment.clone_extend("é/%").to_string(),
+            "#/a%20b/%C3%A9~1%25"
+        );
+        assert_eq!(root(PointerStyle::Fragment).to_string(), "#");
+    }
+
+    #[test]
+    fn sort_key_orders_naturally() {
+        let root = root(PointerStyle::Plain);
+        let items = root.clone_extend("items");
+        let mut pointers = [
+            items.clone_extend(10),
+            root.clone_extend("a\u{0}"),
+            items.clone_extend(2).clone_extend("x"),
+            root.clone_extend("a"),
+            items.clone(),
+            items.clone_extend(2),
+            root.clone_extend("a").clone_extend("b"),
+            root.clone(),
+        ];
+        pointers.sort_by_cached_key(|p| {
+            let mut key = Vec::new();
+            p.write_sort_key(&mut key);
+            key
+        });
+
+        assert_eq!(
+            pointers.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+            vec![
+                "",
+                "/a",
+                "/a/b",
+                "/a\u{0}",
+                "/items",
+                "/items/2",
+                "/items/2/x",
+                "/items/10"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_escaped_pointers() {
+        let tokens = |v: &[&str]| Some(v.iter().map(|s| s.to_string()).collect());
+
+        assert_eq!(parse_pointer(""), tokens(&[]));
+        assert_eq!(parse_pointer("/"), tokens(&[""]));
+        assert_eq!(parse_pointer("/a~1b/~01"), tokens(&["a/b", "~1"]));
+        assert_eq!(parse_pointer("#/a%20b/c"), tokens(&["a b", "c"]));
+        assert_eq!(parse_pointer("#"), tokens(&[]));
+        assert_eq!(parse_pointer("a/b"), None);
+        assert_eq!(parse_pointer("/a~2"), None);
+        assert_eq!(parse_pointer("#/a%2"), None);
+    }
+}
@@ -0,0 +1,328 @@
+use {
+    crate::models::error::{Context, Error, ErrorKind, Result},
+    std::{
+        convert::TryFrom,
+        io::{BufRead, Error as ioError, ErrorKind as ioErrorKind, Read as ioRead},
+    },
+};
+
+#[cfg(feature = "config-file")]
+use serde::Deserialize;
+
+/// Byte sequence marking the end of a record when reading
+/// input line by line, e.g '\n', '\r\n' or '\x1e\n'
+#[cfg_attr(
+    feature = "config-file",
+    derive(Deserialize),
+    serde(try_from = "String")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordSeparator {
+    inner: Vec<u8>,
+}
+
+impl RecordSeparator {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.inner
+    }
+
+    /// The separator's final byte, used as the read_until delimiter
+    fn last(&self) -> u8 {
+        // Construction guarantees a non empty separator
+        *self.inner.last().unwrap()
+    }
+
+    /// Only a plain newline separator implies CRLF stripping
+    fn strips_cr(&self) -> bool {
+        self.inner == b"\n"
+    }
+}
+
+/// Parses a separator, unescaping any of the following:
+/// '\n', '\r', '\t', '\0', '\\' and '\xHH'
+impl TryFrom<&str> for RecordSeparator {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self> {
+        let mut inner = Vec::with_capacity(s.len());
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') => inner.push(b'\n'),
+                    Some('r') => inner.push(b'\r'),
+                    Some('t') => inner.push(b'\t'),
+                    Some('0') => inner.push(b'\0'),
+                    Some('\\') => inner.push(b'\\'),
+                    Some('x') => {
+                        let hex: String = chars.by_ref().take(2).collect();
+                        match u8::from_str_radix(&hex, 16) {
+                            Ok(b) if hex.len() == 2 => inner.push(b),
+                            _ => {
+                                return Err(ErrorKind::Message(format!(
+                                    "'\\x{}' is not a valid hex escape, expected '\\xHH'",
+                                    hex
+                                ))
+                                .into())
+                            }
+                        }
+                    }
+                    Some(other) => {
+                        return Err(ErrorKind::Message(format!(
+                            "'\\{}' is not a supported escape",
+                            other
+                        ))
+                        .into())
+                    }
+                    None => {
+                        return Err(ErrorKind::Message(format!("Trailing '\\' in separator")).into())
+                    }
+                },
+                c => {
+                    let mut buf = [0; 4];
+                    inner.extend_from_slice(c.encode_utf8(&mut buf).as_bytes())
+                }
+            }
+        }
+
+        if inner.is_empty() {
+            Err(ErrorKind::Message(format!("Record separator cannot be empty")).into())
+        } else {
+            Ok(RecordSeparator { inner })
+        }
+    }
+}
+
+impl TryFrom<String> for RecordSeparator {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        RecordSeparator::try_from(s.as_str())
+    }
+}
+
+impl From<u8> for RecordSeparator {
+    fn from(b: u8) -> Self {
+        RecordSeparator { inner: vec![b] }
+    }
+}
+
+impl Default for RecordSeparator {
+    fn default() -> Self {
+        RecordSeparator { inner: vec![b'\n'] }
+    }
+}
+
+/// Splits a buffered read stream into records, each terminated
+/// by a (potentially multi-byte) separator. Records larger than
+/// capacity grow the buffer on demand, up to the ceiling
+pub struct RecordReader<R> {
+    inner: R,
+    separator: RecordSeparator,
+    capacity: usize,
+    ceiling: usize,
+    /// The largest the buffer has grown to, so that
+    /// growth is only reported once per new size
+    grown: usize,
+    line: usize,
+    /// Bytes consumed from inner so far
+    position: u64,
+    buffer: Vec<u8>,
+}
+
+impl<R> RecordReader<R>
+where
+    R: BufRead,
+{
+    pub fn new(separator: RecordSeparator, capacity: usize, ceiling: usize, inner: R) -> Self {
+        RecordReader {
+            inner,
+            separator,
+            capacity,
+            ceiling: ceiling.max(capacity),
+            grown: capacity,
+            line: 0,
+            position: 0,
+            buffer: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The number of bytes consumed, i.e the
+    /// offset of the next record in the stream
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Returns the next record with its separator (and a trailing '\r'
+    /// if the separator is '\n') removed, or None on EOF
+    pub fn next_record(&mut self) -> Option<Result<&[u8]>> {
+        self.buffer.clear();
+        self.line += 1;
+        let mut limit = self.capacity;
+
+        loop {
+            if self.buffer.len() >= limit {
+                if limit >= self.ceiling {
+                    return Some(Err(Error::from((
+                        ioError::new(
+                            ioErrorKind::InvalidData,
+                            format!("record exceeds {} bytes", self.ceiling),
+                        ),
+                        Context::lebm(self.line, self.ceiling),
+                    ))));
+                }
+                limit = self.ceiling;
+            }
+
+            let take = (limit - self.buffer.len()) as u64;
+            match (&mut self.inner)
+                .take(take)
+                .read_until(self.separator.last(), &mut self.buffer)
+            {
+                // EOF
+                Ok(0) => break,
+                Ok(read) if self.buffer.ends_with(self.separator.as_bytes()) => {
+                    self.position += read as u64;
+                    self.note_growth();
+                    let len = self.buffer.len() - self.separator.as_bytes().len();
+                    self.buffer.truncate(len);
+                    if self.separator.strips_cr() && self.buffer.ends_with(b"\r") {
+                        self.buffer.pop();
+                    }
+                    return Some(Ok(&self.buffer));
+                }
+                Ok(read) => self.position += read as u64,
+                Err(e) => return Some(Err(e.into())),
+            }
+        }
+
+        if self.buffer.is_empty() {
+            None
+        } else {
+            self.note_growth();
+            Some(Ok(&self.buffer))
+        }
+    }
+
+    /// Warns if the current record grew the buffer past any earlier one
+    fn note_growth(&mut self) {
+        if self.buffer.len() > self.grown {
+            let grown = self.buffer.capacity().min(self.ceiling);
+            warn!(
+                "Line {} is larger than the input buffer, growing it from {} to {} bytes (at most {})",
+                self.line, self.grown, grown, self.ceiling
+            );
+            self.grown = grown;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::io::Cursor};
+
+    fn collect<S: AsRef<str>>(data: &[u8], sep: S, capacity: usize) -> Vec<Vec<u8>> {
+        let sep = RecordSeparator::try_from(sep.as_ref()).unwrap();
+        let mut reader = RecordReader::new(sep, capacity, capacity * 4, Cursor::new(data));
+        let mut records = Vec::new();
+        while let Some(record) = reader.next_record() {
+            records.push(record.unwrap().to_vec())
+        }
+
+        records
+    }
+
+    #[test]
+    fn separator_escapes() {
+        for (raw, expected) in &[
+            ("\\n", &b"\n"[..]),
+            ("\\r\\n", b"\r\n"),
+            ("\\x1e\\n", b"\x1e\n"),
+            ("||", b"||"),
+            ("\r\n", b"\r\n"),
+            ("\\\\", b"\\"),
+        ] {
+            let sep = RecordSeparator::try_from(*raw).unwrap();
+            assert_eq!(sep.as_bytes(), *expected);
+        }
+    }
+
+    #[test]
+    fn separator_invalid() {
+        for raw in &["", "\\q", "\\x1", "\\xzz", "abc\\"] {
+            assert!(RecordSeparator::try_from(*raw).is_err());
+        }
+    }
+
+    #[test]
+    fn records_strip_crlf() {
+        let records = collect(b"{\"a\":1}\r\n{\"b\":2}\n{\"c\":3}", "\\n", 64);
+
+        assert_eq!(
+            records,
+            vec![
+                b"{\"a\":1}".to_vec(),
+                b"{\"b\":2}".to_vec(),
+                b"{\"c\":3}".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn records_multi_byte_separator() {
+        let records = collect(b"{\"a\":\"x\ny\"}\r\n[1,2]\r\n", "\\r\\n", 64);
+
+        assert_eq!(
+            records,
+            vec![b"{\"a\":\"x\ny\"}".to_vec(), b"[1,2]".to_vec()]
+        );
+    }
+
+    #[test]
+    fn records_grow_past_capacity() {
+        let records = collect(b"0123456789\nab\n", "\\n", 4);
+
+        assert_eq!(records, vec![b"0123456789".to_vec(), b"ab".to_vec()]);
+    }
+
+    #[test]
+    fn growth_noted_once_per_size() {
+        let sep = RecordSeparator::default();
+        let data = &b"0123456789
+01234567
+ab
+"[..];
+        let mut reader = RecordReader::new(sep, 4, 64, Cursor::new(data));
+        let mut grown = Vec::new();
+        while reader.next_record().is_some() {
+            grown.push(reader.grown)
+        }
+
+        assert!(grown[0] >= 10);
+        assert_eq!(grown, vec![grown[0]; 3]);
+    }
+
+    #[test]
+    fn position_counts_separators() {
+        let sep = RecordSeparator::default();
+        let mut reader = RecordReader::new(sep, 4, 16, Cursor::new(&b"ab\r\n0123456789\nc"[..]));
+        let mut positions = vec![reader.position()];
+        while reader.next_record().is_some() {
+            positions.push(reader.position())
+        }
+
+        assert_eq!(positions, vec![0, 4, 15, 16]);
+    }
+
+    #[test]
+    fn records_error_past_ceiling() {
+        let sep = RecordSeparator::default();
+        let mut reader = RecordReader::new(sep, 2, 4, Cursor::new(&b"ab\r\n0123456789\nc"[..]));
+
+        assert_eq!(reader.next_record().unwrap().unwrap(), b"ab");
+        match reader.next_record() {
+            Some(Err(e)) => assert!(format!("{}", e).starts_with("Line 2 ")),
+            _ => panic!("expected an error past the ceiling"),
+        }
+    }
+}
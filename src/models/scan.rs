@@ -1,95 +1,255 @@
-use std::io::Result;
-
-/// Custom JSON scanner, keeps just enough
-/// information to determine if the read stream
-/// is inside quotes and the offsets of the most recent
-/// in-quotes slice and out-quotes slice
-pub struct JsonScan<I> {
-    iter: I,
-    prev: Option<u8>,
+use {
+    memchr::memchr2,
+    std::io::{BufRead, Result},
+};
+
+/// Bytes that end a copy in read_until_structural, when outside quotes
+const STRUCTURAL: [bool; 256] = structural_table();
+
+const fn structural_table() -> [bool; 256] {
+    let mut table = [false; 256];
+    table[b'"' as usize] = true;
+    table[b'[' as usize] = true;
+    table[b'{' as usize] = true;
+    table[b']' as usize] = true;
+    table[b'}' as usize] = true;
+    table[b',' as usize] = true;
+    table
+}
+
+/// Custom JSON scanner, working over whole buffered chunks
+/// at a time. It keeps just enough information to determine if
+/// the read stream is inside quotes, including across chunk
+/// boundaries and escape sequences
+pub struct JsonScan<R> {
+    inner: R,
     state: ScanState,
-    /// (InQuotes, OutQuotes)
-    offsets: (usize, usize),
+    /// Bytes consumed so far
+    position: u64,
 }
 
-impl<I> JsonScan<I>
+impl<R> JsonScan<R>
 where
-    I: Iterator<Item = Result<u8>>,
+    R: BufRead,
 {
-    pub fn new(iter: I) -> JsonScan<I> {
+    pub fn new(inner: R) -> JsonScan<R> {
         JsonScan {
-            iter,
-            prev: None,
+            inner,
             state: ScanState::OutQuotes,
-            offsets: (0, 0),
+            position: 0,
         }
     }
 
+    /// The number of bytes consumed, i.e the offset
+    /// of the next byte in the stream
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
     pub fn outside_quotes(&self) -> bool {
         match self.state {
             ScanState::OutQuotes => true,
-            ScanState::InQuotes => false,
+            ScanState::InQuotes | ScanState::Escaped => false,
         }
     }
 
-    pub fn offsets(&self) -> (usize, usize) {
-        self.offsets
-    }
-
-    fn handle_state(&mut self) {
-        match self.prev {
-            Some(b'\\') => (),
-            _ => match self.state {
-                ScanState::InQuotes => {
-                    self.offsets.1 = 0; // Reset OutQuotes counter
-                    self.state = ScanState::OutQuotes
+    /// Hands every byte up to the next structural byte ('[', '{', ']', '}', ',')
+    /// outside of quotes to sink, a slice at a time. The structural byte
+    /// is consumed and returned, or None if the stream ended first
+    pub fn read_until_structural<F>(&mut self, mut sink: F) -> Result<Option<u8>>
+    where
+        F: FnMut(&[u8]),
+    {
+        loop {
+            let (used, found) = {
+                let chunk = self.inner.fill_buf()?;
+                if chunk.is_empty() {
+                    return Ok(None);
                 }
-                ScanState::OutQuotes => {
-                    self.offsets.0 = 0; // Reset InQuotes counter
-                    self.state = ScanState::InQuotes
+                let mut i = 0;
+                let found = loop {
+                    match self.state {
+                        ScanState::Escaped => {
+                            if i == chunk.len() {
+                                break None;
+                            }
+                            self.state = ScanState::InQuotes;
+                            i += 1;
+                        }
+                        // Jump straight to the next quote or escape
+                        ScanState::InQuotes => match memchr2(b'"', b'\\', &chunk[i..]) {
+                            Some(offset) => {
+                                i += offset;
+                                self.state = match chunk[i] {
+                                    b'"' => ScanState::OutQuotes,
+                                    _ => ScanState::Escaped,
+                                };
+                                i += 1;
+                            }
+                            None => {
+                                i = chunk.len();
+                                break None;
+                            }
+                        },
+                        ScanState::OutQuotes => {
+                            match chunk[i..].iter().position(|b| STRUCTURAL[*b as usize]) {
+                                Some(offset) => {
+                                    i += offset;
+                                    match chunk[i] {
+                                        b'"' => {
+                                            self.state = ScanState::InQuotes;
+                                            i += 1;
+                                        }
+                                        b => break Some(b),
+                                    }
+                                }
+                                None => {
+                                    i = chunk.len();
+                                    break None;
+                                }
+                            }
+                        }
+                    }
+                };
+                sink(&chunk[..i]);
+
+                match found {
+                    // Consume the structural byte too
+                    Some(b) => (i + 1, Some(b)),
+                    None => (i, None),
                 }
-            },
+            };
+            self.inner.consume(used);
+            self.position += used as u64;
+            if found.is_some() {
+                return Ok(found);
+            }
         }
     }
 
-    fn increment_offset(&mut self) {
-        match self.state {
-            ScanState::InQuotes => self.offsets.0 += 1,
-            ScanState::OutQuotes => self.offsets.1 += 1,
-        }
+    /// Returns the next byte, tracking quote state
+    pub fn next_byte(&mut self) -> Result<Option<u8>> {
+        let b = match self.inner.fill_buf()?.first() {
+            Some(b) => *b,
+            None => return Ok(None),
+        };
+        self.inner.consume(1);
+        self.position += 1;
+        self.state = match (self.state, b) {
+            (ScanState::OutQuotes, b'"') => ScanState::InQuotes,
+            (ScanState::OutQuotes, _) => ScanState::OutQuotes,
+            (ScanState::InQuotes, b'"') => ScanState::OutQuotes,
+            (ScanState::InQuotes, b'\\') => ScanState::Escaped,
+            (ScanState::InQuotes, _) => ScanState::InQuotes,
+            (ScanState::Escaped, _) => ScanState::InQuotes,
+        };
+
+        Ok(Some(b))
     }
-}
 
-impl<I> Iterator for JsonScan<I>
-where
-    I: Iterator<Item = Result<u8>>,
-{
-    type Item = Result<u8>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.iter.next() {
-            Some(Ok(b @ b'"')) => {
-                self.handle_state();
-                self.prev = Some(b);
-                Some(Ok(b))
+    /// Returns the next byte that isn't whitespace
+    pub fn next_token(&mut self) -> Result<Option<u8>> {
+        loop {
+            match self.next_byte()? {
+                Some(b) if b.is_ascii_whitespace() => continue,
+                token => return Ok(token),
             }
-            Some(Ok(b)) => {
-                self.increment_offset();
-                self.prev = Some(b);
-                Some(Ok(b))
-            }
-            Some(Err(e)) => {
-                self.increment_offset();
-                self.prev = None;
-                Some(Err(e))
-            }
-            None => None,
         }
     }
+
+    /// Consumes the rest of the stream, without tracking quote state
+    pub fn read_to_end(&mut self, buffer: &mut Vec<u8>) -> Result<usize> {
+        let read = self.inner.read_to_end(buffer)?;
+        self.position += read as u64;
+
+        Ok(read)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq)]
 enum ScanState {
     InQuotes,
+    /// Inside quotes, directly after a '\'
+    Escaped,
     OutQuotes,
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::io::BufReader};
+
+    /// Splits data on structural bytes, using a tiny buffer
+    /// to exercise chunk boundaries
+    fn split(data: &[u8], capacity: usize) -> Vec<(Vec<u8>, Option<u8>)> {
+        let mut scanner = JsonScan::new(BufReader::with_capacity(capacity, data));
+        let mut pieces = Vec::new();
+        loop {
+            let mut piece = Vec::new();
+            let found = scanner
+                .read_until_structural(|s| piece.extend_from_slice(s))
+                .unwrap();
+            pieces.push((piece, found));
+            if found.is_none() {
+                break pieces;
+            }
+        }
+    }
+
+    #[test]
+    fn structural_outside_quotes() {
+        for capacity in &[1, 2, 3, 64] {
+            assert_eq!(
+                split(br#"{"a,]":[1,"}"]}"#, *capacity),
+                vec![
+                    (b"".to_vec(), Some(b'{')),
+                    (br#""a,]":"#.to_vec(), Some(b'[')),
+                    (b"1".to_vec(), Some(b',')),
+                    (br#""}""#.to_vec(), Some(b']')),
+                    (b"".to_vec(), Some(b'}')),
+                    (b"".to_vec(), None),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn escaped_backslash_before_quote() {
+        for capacity in &[1, 2, 3, 64] {
+            assert_eq!(
+                split(br#"["a\\",["b\"]"]]"#, *capacity),
+                vec![
+                    (b"".to_vec(), Some(b'[')),
+                    (br#""a\\""#.to_vec(), Some(b',')),
+                    (b"".to_vec(), Some(b'[')),
+                    (br#""b\"]""#.to_vec(), Some(b']')),
+                    (b"".to_vec(), Some(b']')),
+                    (b"".to_vec(), None),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn next_byte_tracks_quotes() {
+        let mut scanner = JsonScan::new(&br#""\\" x"#[..]);
+        let mut states = Vec::new();
+        while scanner.next_byte().unwrap().is_some() {
+            states.push(scanner.outside_quotes())
+        }
+
+        assert_eq!(states, vec![false, false, false, true, true, true]);
+        assert_eq!(scanner.position(), 6);
+    }
+
+    #[test]
+    fn position_counts_consumed_bytes() {
+        let mut scanner = JsonScan::new(BufReader::with_capacity(2, &br#"{"a": [1]} 2"#[..]));
+        let mut positions = Vec::new();
+        while let Some(b) = scanner.read_until_structural(|_| ()).unwrap() {
+            positions.push((b, scanner.position()))
+        }
+
+        assert_eq!(positions, vec![(b'{', 1), (b'[', 7), (b']', 9), (b'}', 10)]);
+        assert_eq!(scanner.position(), 12);
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+/// What a string value appears to hold, beyond being a string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemType {
+    Numeric,
+    Date,
+    DateTime,
+    Uuid,
+    IPv4,
+    IPv6,
+    Email,
+    Url,
+    Hex,
+    Base64,
+}
+
+impl SemType {
+    /// Classifies a string, None if it fits none of the types. Each
+    /// check is a single pass (at most) over the string's bytes, and
+    /// they are ordered so that the narrower types win, e.g '1234' is
+    /// Numeric rather than Hex
+    pub fn classify(s: &str) -> Option<Self> {
+        let b = s.as_bytes();
+        match () {
+            _ if b.is_empty() => None,
+            _ if is_numeric(b) => Some(SemType::Numeric),
+            _ if b.len() == 10 && is_date(b) => Some(SemType::Date),
+            _ if b.len() > 10 && is_date(&b[..10]) && is_time(&b[10..]) => Some(SemType::DateTime),
+            _ if is_uuid(b) => Some(SemType::Uuid),
+            _ if s.parse::<Ipv4Addr>().is_ok() => Some(SemType::IPv4),
+            _ if b.contains(&b':') && s.parse::<Ipv6Addr>().is_ok() => Some(SemType::IPv6),
+            _ if is_email(b) => Some(SemType::Email),
+            _ if is_url(b) => Some(SemType::Url),
+            _ if is_hex(b) => Some(SemType::Hex),
+            _ if is_base64(b) => Some(SemType::Base64),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SemType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            SemType::Numeric => "Numeric",
+            SemType::Date => "Date",
+            SemType::DateTime => "DateTime",
+            SemType::Uuid => "Uuid",
+            SemType::IPv4 => "IPv4",
+            SemType::IPv6 => "IPv6",
+            SemType::Email => "Email",
+            SemType::Url => "Url",
+            SemType::Hex => "Hex",
+            SemType::Base64 => "Base64",
+        };
+
+        write!(f, "{}", kind)
+    }
+}
+
+/// Counts the leading ASCII digits
+fn digits(b: &[u8]) -> usize {
+    b.iter().take_while(|b| b.is_ascii_digit()).count()
+}
+
+/// Parses exactly 'len' digits at the start of 'b'
+fn number(b: &[u8], len: usize) -> Option<u32> {
+    match b.get(..len) {
+        Some(n) if digits(n) == len => Some(
+            n.iter()
+                .fold(0, |acc, digit| acc * 10 + u32::from(digit - b'0')),
+        ),
+        _ => None,
+    }
+}
+
+/// A decimal number, as JSON would write it (but allowing a leading
+/// '+'), e.g '-12', '0.5' or '1e10'
+fn is_numeric(b: &[u8]) -> bool {
+    let b = match b.first() {
+        Some(b'-') | Some(b'+') => &b[1..],
+        _ => b,
+    };
+    let int = digits(b);
+    if int == 0 {
+        return false;
+    }
+    let mut rest = &b[int..];
+    if rest.first() == Some(&b'.') {
+        match digits(&rest[1..]) {
+            0 => return false,
+            frac => rest = &rest[1 + frac..],
+        }
+    }
+    if let Some(b'e') | Some(b'E') = rest.first() {
+        rest = match rest.get(1) {
+            Some(b'-') | Some(b'+') => &rest[2..],
+            _ => &rest[1..],
+        };
+        match digits(rest) {
+            0 => return false,
+            exp => rest = &rest[exp..],
+        }
+    }
+
+    rest.is_empty()
+}
+
+/// An ISO 8601 calendar date, 'YYYY-MM-DD'
+fn is_date(b: &[u8]) -> bool {
+    b.len() == 10
+        && number(b, 4).is_some()
+        && b[4] == b'-'
+        && number(&b[5..], 2).map_or(false, |month| (1..=12).contains(&month))
+        && b[7] == b'-'
+        && number(&b[8..], 2).map_or(false, |day| (1..=31).contains(&day))
+}
+
+/// The time part of an ISO 8601 datetime, i.e what follows the
+/// date: 'THH:MM', optionally with seconds (and their fraction) and
+/// a 'Z' or '+HH:MM' offset. A space is accepted in place of the 'T'
+fn is_time(b: &[u8]) -> bool {
+    let hm = |b: &[u8]| {
+        number(b, 2).map_or(false, |h| h <= 23)
+            && b.get(2) == Some(&b':')
+            && number(&b[3..], 2).map_or(false, |m| m <= 59)
+    };
+    match b.first() {
+        Some(b'T') | Some(b't') | Some(b' ') if b.len() >= 6 && hm(&b[1..]) => {}
+        _ => return false,
+    }
+    let mut rest = &b[6..];
+    if rest.first() == Some(&b':') {
+        match number(&rest[1..], 2) {
+            Some(s) if s <= 60 => rest = &rest[3..],
+            _ => return false,
+        }
+        if let Some(b'.') | Some(b',') = rest.first() {
+            match digits(&rest[1..]) {
+                0 => return false,
+                frac => rest = &rest[1 + frac..],
+            }
+        }
+    }
+
+    match rest {
+        [] | [b'Z'] | [b'z'] => true,
+        [b'+', offset @ ..] | [b'-', offset @ ..] => match offset.len() {
+            5 => hm(offset),
+            4 => number(offset, 4).is_some(),
+            2 => number(offset, 2).is_some(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// 'xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx', in hex digits of either case
+fn is_uuid(b: &[u8]) -> bool {
+    b.len() == 36
+        && b.iter().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => *b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        })
+}
+
+/// 'local@domain.tld', loosely: a single '@', no whitespace, and
+/// a domain with a dot that neither starts nor ends it
+fn is_email(b: &[u8]) -> bool {
+    let mut parts = b.splitn(2, |b| *b == b'@');
+    match (parts.next(), parts.next()) {
+        (Some(local), Some(domain)) => {
+            !local.is_empty()
+                && !b.iter().any(|b| b.is_ascii_whitespace())
+                && !domain.contains(&b'@')
+                && domain.contains(&b'.')
+                && !domain.starts_with(b".")
+                && !domain.ends_with(b".")
+        }
+        _ => false,
+    }
+}
+
+/// 'scheme://rest', the scheme being a letter followed by letters,
+/// digits, '+', '-' or '.', and the rest non empty without whitespace
+fn is_url(b: &[u8]) -> bool {
+    let scheme = b
+        .iter()
+        .take_while(|b| b.is_ascii_alphanumeric() || b"+-.".contains(b))
+        .count();
+
+    scheme > 0
+        && b[0].is_ascii_alphabetic()
+        && b[scheme..].starts_with(b"://")
+        && b.len() > scheme + 3
+        && !b.iter().any(|b| b.is_ascii_whitespace())
+}
+
+/// An even number of hex digits, at least 8 of them, e.g a digest or
+/// encoded bytes. At least one must be a decimal digit, so that words
+/// made only of the letters a-f, e.g 'cafe' or 'decade', aren't taken
+/// for hex, and short codes like 'a1' or '42ab' are left alone
+fn is_hex(b: &[u8]) -> bool {
+    b.len() >= 8
+        && b.len() % 2 == 0
+        && b.iter().all(u8::is_ascii_hexdigit)
+        && b.iter().any(u8::is_ascii_digit)
+}
+
+/// Standard base64, not just letters, and either using one of '+', '/'
+/// or '=' padding, or at least 16 characters long. Identifiers like
+/// 'user1234' or 'Passw0rd' fit the alphabet too, but are rarely that long
+fn is_base64(b: &[u8]) -> bool {
+    let data = match b {
+        [data @ .., b'=', b'='] | [data @ .., b'='] => data,
+        data => data,
+    };
+
+    b.len() >= 8
+        && b.len() % 4 == 0
+        && data
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'+' || *b == b'/')
+        && !b.iter().all(u8::is_ascii_alphabetic)
+        && (b.len() >= 16 || b.iter().any(|b| matches!(b, b'+' | b'/' | b'=')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classified(values: &[&str]) -> Vec<Option<SemType>> {
+        values.iter().map(|s| SemType::classify(s)).collect()
+    }
+
+    #[test]
+    fn classify_each_type() {
+        use SemType::*;
+
+        for (s, expected) in &[
+            ("-12.5e3", Numeric),
+            ("2019-07-31", Date),
+            ("2019-07-31T23:59:60.123Z", DateTime),
+            ("2019-07-31 08:15+02:00", DateTime),
+            ("123e4567-E89B-12d3-a456-426614174000", Uuid),
+            ("192.168.0.1", IPv4),
+            ("::1", IPv6),
+            ("fe80::1ff:fe23:4567:890a", IPv6),
+            ("jane.doe+tag@example.co.uk", Email),
+            ("https://example.com/a?b=c", Url),
+            ("deadBEEF00", Hex),
+            ("aGVsbG8gd29ybGQ=", Base64),
+            ("aGVsbG8gd29ybGQh", Base64),
+            ("ab+/cd12", Base64),
+        ] {
+            assert_eq!(SemType::classify(s), Some(*expected), "{}", s);
+        }
+    }
+
+    #[test]
+    fn classify_nothing() {
+        assert_eq!(
+            classified(&[
+                "",
+                "hello",
+                "Password",
+                "1.",
+                "2019-13-01",
+                "2019-07-31T24:00",
+                "2019-07-31Tnoon",
+                "256.0.0.1",
+                "@example.com",
+                "a@b",
+                "a b@example.com",
+                "example.com",
+                "://example.com",
+                "abc",
+                "cafe",
+                "beef",
+                "decade",
+                "aGVsbG8gd29ybGQ",
+                "version2",
+                "user1234",
+                "Passw0rd",
+                "SKU00042",
+                "a1",
+                "42ab",
+            ]),
+            vec![None; 24]
+        );
+    }
+}
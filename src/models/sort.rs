@@ -0,0 +1,273 @@
+use {
+    crate::models::{
+        block::BlockKind,
+        builder::{Builder, Output},
+        error::Result,
+        field::Field,
+    },
+    std::{
+        cmp::{Ordering, Reverse},
+        collections::BinaryHeap,
+        env::temp_dir,
+        fs::{remove_file, File, OpenOptions},
+        io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+        mem::take,
+        path::PathBuf,
+        process,
+        sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+    },
+};
+
+/// Rough per row bookkeeping cost, on top of its bytes
+const ROW_OVERHEAD: usize = 32;
+
+/// Keeps run file names unique within the process
+static RUN_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Writes the key an Output is sorted by: its identifier, then its
+/// pointer. Rows missing either sort before those that have them
+pub fn write_sort_key(output: &Output, key: &mut Vec<u8>) {
+    match <Output as Builder<Field>>::identifer(output) {
+        Ok(BlockKind::Ident(i)) => {
+            key.push(1);
+            key.extend_from_slice(&(*i.as_ref() as u64).to_be_bytes())
+        }
+        _ => key.push(0),
+    }
+    if let Ok(BlockKind::Pointer(p)) = <Output as Builder<Field>>::pointer(output) {
+        p.as_ref().write_sort_key(key)
+    }
+}
+
+/// A row, its first 'key' bytes being its sort key
+/// and the remainder the formatted output line
+struct Row {
+    key: usize,
+    bytes: Box<[u8]>,
+}
+
+impl Row {
+    fn key(&self) -> &[u8] {
+        &self.bytes[..self.key]
+    }
+
+    fn line(&self) -> &[u8] {
+        &self.bytes[self.key..]
+    }
+
+    fn write_to<W: Write>(&self, w: &mut W) -> Result<()> {
+        w.write_all(&(self.key as u64).to_le_bytes())?;
+        w.write_all(&(self.bytes.len() as u64).to_le_bytes())?;
+        w.write_all(&self.bytes)?;
+
+        Ok(())
+    }
+
+    /// Reads the next row of a run, None if the run is exhausted
+    fn read_from<R: Read>(r: &mut R) -> Result<Option<Self>> {
+        let mut word = [0; 8];
+        match r.read_exact(&mut word) {
+            Ok(_) => {}
+            Err(ref e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let key = u64::from_le_bytes(word) as usize;
+        r.read_exact(&mut word)?;
+        let mut bytes = vec![0; u64::from_le_bytes(word) as usize].into_boxed_slice();
+        r.read_exact(&mut bytes)?;
+
+        Ok(Some(Row { key, bytes }))
+    }
+}
+
+/// A sorted run spilled to disk, removed once dropped
+struct Run {
+    path: PathBuf,
+    file: File,
+}
+
+impl Run {
+    fn create() -> Result<Self> {
+        let n = RUN_COUNT.fetch_add(1, AtomicOrdering::Relaxed);
+        let path = temp_dir().join(format!("jaesve-{}-{}.run", process::id(), n));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        Ok(Run { path, file })
+    }
+}
+
+impl Drop for Run {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
+
+/// The head of a run during the merge, ordered by key and then by
+/// run, so that rows with equal keys keep the order they arrived in
+struct Head {
+    row: Row,
+    run: usize,
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.row
+            .key()
+            .cmp(other.row.key())
+            .then(self.run.cmp(&other.run))
+    }
+}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+/// Collects rows, writing them out sorted by key once finished.
+/// Rows with equal keys are written in the order they were pushed.
+/// Whenever the rows held grow past 'limit' bytes they are sorted
+/// and spilled to a temporary file, the runs being merged at the end
+pub struct Sorter {
+    rows: Vec<Row>,
+    held: usize,
+    limit: usize,
+    runs: Vec<Run>,
+}
+
+impl Sorter {
+    pub fn new(limit: usize) -> Self {
+        Sorter {
+            rows: Vec::new(),
+            held: 0,
+            limit,
+            runs: Vec::new(),
+        }
+    }
+
+    /// Adds a row, 'key' being its leading 'key' bytes
+    pub fn push(&mut self, key: usize, bytes: Vec<u8>) -> Result<()> {
+        self.held += bytes.len() + ROW_OVERHEAD;
+        self.rows.push(Row {
+            key,
+            bytes: bytes.into_boxed_slice(),
+        });
+        if self.held >= self.limit {
+            self.spill()?
+        }
+
+        Ok(())
+    }
+
+    /// Writes every row's line, in order
+    pub fn finish<W: Write>(mut self, w: &mut W) -> Result<()> {
+        // Everything fit in memory, no need to touch the disk
+        if self.runs.is_empty() {
+            self.rows.sort_by(|a, b| a.key().cmp(b.key()));
+            for row in self.rows.iter() {
+                w.write_all(row.line())?
+            }
+            return Ok(());
+        }
+        self.spill()?;
+        debug!("Merging {} sorted runs", self.runs.len());
+
+        let mut readers = self
+            .runs
+            .iter_mut()
+            .map(|run| {
+                run.file.seek(SeekFrom::Start(0))?;
+                Ok(BufReader::new(&mut run.file))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+        for (run, reader) in readers.iter_mut().enumerate() {
+            if let Some(row) = Row::read_from(reader)? {
+                heap.push(Reverse(Head { row, run }))
+            }
+        }
+        while let Some(Reverse(Head { row, run })) = heap.pop() {
+            w.write_all(row.line())?;
+            if let Some(row) = Row::read_from(&mut readers[run])? {
+                heap.push(Reverse(Head { row, run }))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sorts the rows held and writes them to a new run
+    fn spill(&mut self) -> Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let mut rows = take(&mut self.rows);
+        self.held = 0;
+        rows.sort_by(|a, b| a.key().cmp(b.key()));
+
+        let mut run = Run::create()?;
+        {
+            let mut writer = BufWriter::new(&mut run.file);
+            for row in rows.iter() {
+                row.write_to(&mut writer)?
+            }
+            writer.flush()?;
+        }
+        debug!("Spilled {} rows to {}", rows.len(), run.path.display());
+        self.runs.push(run);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(limit: usize, rows: &[(&str, &str)]) -> Result<String> {
+        let mut sorter = Sorter::new(limit);
+        for (key, line) in rows {
+            sorter.push(key.len(), [*key, *line].concat().into_bytes())?;
+        }
+        let mut out = Vec::new();
+        sorter.finish(&mut out)?;
+
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn sorts_in_memory() -> Result<()> {
+        let rows = [("b", "1 "), ("a", "2 "), ("b", "3 "), ("a", "4 ")];
+
+        assert_eq!(sorted(usize::MAX, &rows)?, "2 4 1 3 ");
+        Ok(())
+    }
+
+    #[test]
+    fn merges_spilled_runs() -> Result<()> {
+        let rows = [
+            ("c", "1 "),
+            ("a", "2 "),
+            ("b", "3 "),
+            ("a", "4 "),
+            ("c", "5 "),
+        ];
+
+        // Spills every row to its own run
+        assert_eq!(sorted(1, &rows)?, "2 4 3 1 5 ");
+        assert_eq!(sorted(2 * ROW_OVERHEAD + 6, &rows)?, "2 4 3 1 5 ");
+        Ok(())
+    }
+}
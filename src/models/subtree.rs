@@ -0,0 +1,278 @@
+use {
+    crate::{
+        cli::ProgramArgs,
+        models::{error::Result, field::Field},
+    },
+    serde_json::{
+        from_slice, to_writer, Value as Json,
+        Value::{Array as jArray, Object as jObject},
+    },
+};
+
+/// What a container unwound as a packet of its own looked like, before
+/// it was left behind in its parent's packet as an empty '[]' or '{}'.
+/// Carried with the parent's packet, so that the container's row can
+/// still describe it, without the parent holding on to its children
+#[derive(Debug, Default)]
+pub struct Subtree {
+    size: usize,
+    raw: Option<String>,
+    hash: Option<String>,
+}
+
+impl Subtree {
+    /// Summarizes the container in buffer, which has size children. Its own
+    /// children (if any) were collapsed and summarized in children. Only
+    /// the parts that are output are calculated, size being counted as
+    /// the container is read
+    pub fn of(
+        opts: &ProgramArgs,
+        size: usize,
+        buffer: &[u8],
+        children: Option<&Subtrees>,
+    ) -> Result<Self> {
+        let mut subtree = Subtree {
+            size,
+            ..Subtree::default()
+        };
+        let (raw, hash) = (
+            opts.should_calculate(Field::Raw),
+            opts.should_calculate(Field::Hash),
+        );
+        if raw || hash {
+            // Only as deep as the container itself, its children being collapsed
+            let json: Json = from_slice(buffer)?;
+            if raw {
+                subtree.raw = Some(render(&json, children));
+            }
+            if hash {
+                subtree.hash = Some(opts.hash_algorithm().digest_with(&json, children));
+            }
+        }
+
+        Ok(subtree)
+    }
+
+    /// Checks if any output describes a container by its children,
+    /// and thus needs them summarized before they are collapsed
+    pub fn is_needed(opts: &ProgramArgs) -> bool {
+        opts.should_calculate(Field::Raw)
+            || opts.should_calculate(Field::Size)
+            || opts.should_calculate(Field::Hash)
+            || (opts.value_size() && opts.should_calculate(Field::Value))
+    }
+
+    /// The number of elements (or members) of the container
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    pub fn hash(&self) -> Option<&str> {
+        self.hash.as_deref()
+    }
+}
+
+/// The subtrees of a packet's children, in the
+/// packet they are the empty containers
+#[derive(Debug)]
+pub enum Subtrees {
+    /// By position, in order
+    Array(Vec<(usize, Subtree)>),
+    /// Sorted by key (once finished), keeping the source order of duplicates
+    Object(Vec<(String, Subtree)>),
+}
+
+impl Subtrees {
+    /// Subtrees of the container opened by prefix_byte
+    pub fn new(prefix_byte: u8) -> Self {
+        match prefix_byte {
+            b'[' => Subtrees::Array(Vec::new()),
+            _ => Subtrees::Object(Vec::new()),
+        }
+    }
+
+    pub fn push_element(&mut self, index: usize, subtree: Subtree) {
+        if let Subtrees::Array(ref mut elements) = self {
+            elements.push((index, subtree))
+        }
+    }
+
+    pub fn push_member(&mut self, key: String, subtree: Subtree) {
+        if let Subtrees::Object(ref mut members) = self {
+            members.push((key, subtree))
+        }
+    }
+
+    /// Readies the subtrees for lookups, called once all are pushed
+    pub fn finish(mut self) -> Self {
+        if let Subtrees::Object(ref mut members) = self {
+            // Stable, so duplicate keys keep their order
+            members.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        self
+    }
+
+    pub fn element(&self, index: usize) -> Option<&Subtree> {
+        match self {
+            Subtrees::Array(ref elements) => elements
+                .binary_search_by_key(&index, |(i, _)| *i)
+                .ok()
+                .map(|i| &elements[i].1),
+            _ => None,
+        }
+    }
+
+    /// The subtree of the member with the given key, if a key
+    /// is repeated the last one is used, as serde does
+    pub fn member(&self, key: &str) -> Option<&Subtree> {
+        match self {
+            Subtrees::Object(ref members) => {
+                match members.partition_point(|(k, _)| k.as_str() <= key) {
+                    0 => None,
+                    i => Some(&members[i - 1])
+                        .filter(|(k, _)| k == key)
+                        .map(|(_, s)| s),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Writes a value as compact JSON, writing any of
+/// its children found in children as they were
+fn render(json: &Json, children: Option<&Subtrees>) -> String {
+    let mut buf = Vec::new();
+    // Writing to a Vec can't fail
+    match (json, children) {
+        (jObject(map), Some(children)) => {
+            buf.push(b'{');
+            for (i, (k, v)) in map.iter().enumerate() {
+                if i > 0 {
+                    buf.push(b',')
+                }
+                let _ = to_writer(&mut buf, k);
+                buf.push(b':');
+                match children.member(k).and_then(Subtree::raw) {
+                    Some(raw) => buf.extend_from_slice(raw.as_bytes()),
+                    None => {
+                        let _ = to_writer(&mut buf, v);
+                    }
+                }
+            }
+            buf.push(b'}');
+        }
+        (jArray(a), Some(children)) => {
+            buf.push(b'[');
+            for (i, v) in a.iter().enumerate() {
+                if i > 0 {
+                    buf.push(b',')
+                }
+                match children.element(i).and_then(Subtree::raw) {
+                    Some(raw) => buf.extend_from_slice(raw.as_bytes()),
+                    None => {
+                        let _ = to_writer(&mut buf, v);
+                    }
+                }
+            }
+            buf.push(b']');
+        }
+        (json, _) => {
+            let _ = to_writer(&mut buf, json);
+        }
+    }
+
+    // Only ever written valid UTF8
+    String::from_utf8(buf).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::models::hash::HashAlgorithm, serde_json::from_str};
+
+    fn raw(raw: &str) -> Subtree {
+        Subtree {
+            size: 0,
+            raw: Some(raw.to_string()),
+            hash: None,
+        }
+    }
+
+    #[test]
+    fn render_collapsed_children() {
+        let mut children = Subtrees::new(b'{');
+        children.push_member("b".into(), raw(r#"{"c":[1,2]}"#));
+        children.push_member("a".into(), raw("[]"));
+        let children = children.finish();
+        let json = from_str(r#"{"b": {}, "a": [], "d": "x\"y"}"#).unwrap();
+
+        let rendered = render(&json, Some(&children));
+        if cfg!(feature = "preserve-order") {
+            assert_eq!(rendered, r#"{"b":{"c":[1,2]},"a":[],"d":"x\"y"}"#);
+        } else {
+            assert_eq!(rendered, r#"{"a":[],"b":{"c":[1,2]},"d":"x\"y"}"#);
+        }
+    }
+
+    #[test]
+    fn render_collapsed_elements() {
+        let mut children = Subtrees::new(b'[');
+        children.push_element(1, raw(r#"{"a":null}"#));
+        let children = children.finish();
+        let json = from_str(r#"[1, {}, "2"]"#).unwrap();
+
+        assert_eq!(render(&json, Some(&children)), r#"[1,{"a":null},"2"]"#);
+        assert!(children.element(0).is_none());
+    }
+
+    #[test]
+    fn digest_of_collapsed_children() {
+        let algo = HashAlgorithm::Sha256;
+        let whole = from_str(r#"{"a": [1, {"b": null}], "c": {}}"#).unwrap();
+        let element = from_str(r#"{"b": null}"#).unwrap();
+        let mut grandchildren = Subtrees::new(b'[');
+        grandchildren.push_element(
+            1,
+            Subtree {
+                size: 1,
+                raw: None,
+                hash: Some(algo.digest(&element)),
+            },
+        );
+        let collapsed = from_str(r#"[1, {}]"#).unwrap();
+        let mut children = Subtrees::new(b'{');
+        children.push_member(
+            "a".into(),
+            Subtree {
+                size: 2,
+                raw: None,
+                hash: Some(algo.digest_with(&collapsed, Some(&grandchildren.finish()))),
+            },
+        );
+        let root = from_str(r#"{"a": [], "c": {}}"#).unwrap();
+
+        assert_eq!(
+            algo.digest_with(&root, Some(&children.finish())),
+            algo.digest(&whole)
+        );
+        assert_ne!(algo.digest(&root), algo.digest(&whole));
+    }
+
+    #[test]
+    fn subtree_of_duplicate_keys() {
+        let mut children = Subtrees::new(b'{');
+        children.push_member("a".into(), raw("[1]"));
+        children.push_member("b".into(), raw("[2]"));
+        children.push_member("a".into(), raw("[3]"));
+        let children = children.finish();
+
+        assert_eq!(children.member("a").and_then(Subtree::raw), Some("[3]"));
+        assert_eq!(children.member("b").and_then(Subtree::raw), Some("[2]"));
+        assert!(children.member("c").is_none());
+    }
+}
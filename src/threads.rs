@@ -3,125 +3,245 @@ use {
     crate::{
         models::{
             assets::{BlockGenerator, JsonPacket, OrDisplay, ReadKind},
+            batch::{BatchSender, SendBatch},
+            block::{LineNumber, RowNumber, SourcePath},
             check_index,
-            error::{Context, ErrContext, ErrorKind, Result},
+            detect::{InputFormat, RECORD_SEPARATOR},
+            error::{Context, ErrContext, Error, ErrorKind, Result},
             eval,
             field::Field,
-            get_writer, unwind_json, write_formatted_output, ToBuilder, ToWriter,
+            get_writer,
+            origin::Origin,
+            record::{RecordReader, RecordSeparator},
+            sort::{write_sort_key, Sorter},
+            unwind_concatenated, unwind_json, write_formatted_output, write_header, ToBuilder,
+            ToBuilderTx, ToWriter,
         },
         with_log, CLI,
     },
-    linereader::LineReader,
     std::{
+        collections::BTreeMap,
         convert::TryFrom,
-        io::{BufReader, BufWriter, Read as ioRead},
+        io::{BufRead, BufReader, BufWriter, Cursor, Read},
         str::from_utf8,
-        sync::mpsc::{sync_channel as syncQueue, Receiver, SyncSender},
+        sync::{
+            mpsc::{channel as queue, sync_channel as syncQueue, Receiver, Sender, SyncSender},
+            Arc, Mutex,
+        },
         thread::{Builder as Thread, JoinHandle},
     },
 };
 
 type WorkerHandle = JoinHandle<Result<()>>;
+/// An input source, its ident and where its output should go
+type Job = (Option<usize>, ReadKind, WriteSink);
+/// Batches output blocks before sending them to the writer
+type ToWriterTx = BatchSender<ToWriter, DataSender>;
+/// A batch of packets, tagged with its position in the input
+type PoolJob = (usize, Vec<ToBuilder>);
+
+/// Sending half of a single input's output channel
+enum DataSender {
+    /// Used when there's a single worker, the writer paces the builder
+    Bounded(SyncSender<Vec<ToWriter>>),
+    /// Used when workers run in parallel, so that a worker
+    /// never waits on the writer to finish another input
+    Unbounded(Sender<Vec<ToWriter>>),
+}
+
+impl SendBatch<ToWriter> for DataSender {
+    fn send_batch(&self, batch: Vec<ToWriter>) -> Result<()> {
+        match self {
+            DataSender::Bounded(tx) => tx.send_batch(batch),
+            DataSender::Unbounded(tx) => tx.send_batch(batch),
+        }
+    }
+}
+
+/// Destination of a single input's output
+enum WriteSink {
+    /// Already handed to the writer, in input order
+    Ordered(DataSender),
+    /// Handed to the writer once the input is complete
+    Unordered(SyncSender<Receiver<Vec<ToWriter>>>),
+}
 
 /// Spawns workers and the channels which communicate input segments.
 /// Each input source "from_source" is assigned a new channel, and said channel's
 /// rx sent through the "meta channels." This implementation ensures that the control
-/// flow mirrors the data flow
+/// flow mirrors the data flow.
+///
+/// Inputs are shared between '--jobs' pairs of Reader -> Builder workers, the
+/// Dispatcher handing each input's output channel to the writer in input order,
+/// unless '--unordered' is set, in which case the Builder hands it off on completion.
+/// Data is moved between workers in batches of 'batch_size' items
 pub(crate) fn spawn_workers(from_source: Receiver<ReadKind>) -> Result<WorkerHandle> {
-    // Meta channel: |Reader -> Builder|, delivers new receivers to builder
-    let (re_bu_tx, re_bu_rx) = syncQueue(0);
-    // Meta channel: |Builder -> Writer|, delivers new receivers to writer
-    let (bu_wr_tx, bu_wr_rx) = syncQueue(0);
+    // Meta channel: |Dispatcher/Builder(s) -> Writer|, delivers new receivers to writer
+    let (to_wr_tx, to_wr_rx) = syncQueue(CLI.jobs());
 
     // Writer
-    let writer_h = worker_writer(bu_wr_rx)?;
+    let writer_h = worker_writer(to_wr_rx)?;
+
+    // Job queue: |Dispatcher -> Reader(s)|, shared between all readers
+    let (job_tx, job_rx) = syncQueue(0);
+    let job_rx = Arc::new(Mutex::new(job_rx));
 
-    // Builder
-    let builder_h = worker_builder(re_bu_rx, bu_wr_tx, writer_h)?;
+    // Reader(s) -> Builder(s)
+    let workers = (1..=CLI.jobs())
+        .map(|n| {
+            // Meta channel: |Reader -> Builder|, delivers new receivers to builder
+            let (re_bu_tx, re_bu_rx) = syncQueue(0);
+            let builder_h = worker_builder(n, re_bu_rx)?;
+            worker_reader(n, re_bu_tx, builder_h, Arc::clone(&job_rx))
+        })
+        .collect::<Result<Vec<WorkerHandle>>>()?;
 
-    // Reader
-    worker_reader(re_bu_tx, builder_h, from_source)
+    // Dispatcher
+    worker_dispatcher(from_source, job_tx, to_wr_tx, workers, writer_h)
+}
+
+/// Assigns an ident and an output destination to each input source,
+/// before queuing it for the next free reader
+fn worker_dispatcher(
+    read_rx: Receiver<ReadKind>,
+    job_tx: SyncSender<Job>,
+    m_chan_tx: SyncSender<Receiver<Vec<ToWriter>>>,
+    workers: Vec<WorkerHandle>,
+    writer_h: WorkerHandle,
+) -> Result<WorkerHandle> {
+    Thread::new()
+        .name(format!("Dispatcher"))
+        .spawn(move || -> Result<()> {
+            debug!("Dispatcher initialized");
+            let result = || -> Result<()> {
+                let iter = eval(&Field::Identifier, lazy_eval_ident, &read_rx);
+                // Hot loop
+                for (i, read) in iter {
+                    let sink = match (CLI.unordered(), CLI.jobs()) {
+                        (true, _) => WriteSink::Unordered(m_chan_tx.clone()),
+                        (false, 1) => {
+                            let (data_tx, data_rx) = syncQueue(CLI.channel_depth());
+                            m_chan_tx.send(data_rx).context(Context::umcc("Writer"))?;
+                            WriteSink::Ordered(DataSender::Bounded(data_tx))
+                        }
+                        (false, _) => {
+                            let (data_tx, data_rx) = queue();
+                            m_chan_tx.send(data_rx).context(Context::umcc("Writer"))?;
+                            WriteSink::Ordered(DataSender::Unbounded(data_tx))
+                        }
+                    };
+                    job_tx
+                        .send((i, read, sink))
+                        .context(Context::umcc("Reader"))?;
+                }
+
+                Ok(())
+            };
+            // Cleanup
+            let deferred = result();
+            drop(job_tx);
+            // Every worker is joined, keeping the first error (if any)
+            let mut joined = deferred;
+            for handle in workers {
+                let res = match handle.join() {
+                    Ok(inner) => inner,
+                    Err(_) => Err(Error::from((
+                        ErrorKind::ThreadFailed,
+                        Context::tp("Reader"),
+                    ))),
+                };
+                joined = joined.and(res);
+            }
+            thread_cleanup(joined, writer_h, m_chan_tx)
+        })
+        .map_err(|e| e.into())
 }
 
 /// Handles the majority of json doc unwinding and parsing.
 /// The exact work it does depends on what runtime constraints exist
 /// for program output
 fn worker_reader(
-    m_chan_tx: SyncSender<Receiver<ToBuilder>>,
+    n: usize,
+    m_chan_tx: SyncSender<(Receiver<Vec<ToBuilder>>, WriteSink, bool)>,
     builder_h: WorkerHandle,
-    read_rx: Receiver<ReadKind>,
+    job_rx: Arc<Mutex<Receiver<Job>>>,
 ) -> Result<WorkerHandle> {
     Thread::new()
-        .name(format!("Reader"))
+        .name(format!("Reader-{}", n))
         .spawn(move || -> Result<()> {
             debug!("Reader initialized");
             let result = || -> Result<()> {
-                let iter = eval(&Field::Identifier, lazy_eval_ident, &read_rx);
                 // Hot loop
-                for item in iter {
-                    let (data_tx, data_rx): (SyncSender<ToBuilder>, Receiver<ToBuilder>) =
-                        syncQueue(10);
-                    m_chan_tx.send(data_rx).context(Context::umcc("Builder"))?;
-                    match (item, &CLI.by_line()) {
-                        ((i, read @ ReadKind::Stdin(_)), true) => {
-                            let mut line_reader = LineReader::with_delimiter_and_capacity(
-                                CLI.linereader_eol(),
-                                CLI.input_buffer_size(),
-                                read.into_inner(),
+                loop {
+                    // The lock must be released before processing the job, a poisoned
+                    // lock only means another reader panicked, the queue itself is fine
+                    let job = job_rx
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .recv();
+                    let (i, read, sink) = match job {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    let source = match should_track_origin() {
+                        true => Some(read.source()),
+                        false => None,
+                    };
+                    let format = match (CLI.input_format(), &read) {
+                        (Some(format), _) => format,
+                        (None, ReadKind::Stdin(_)) if CLI.by_line() => InputFormat::Lines,
+                        (None, _) => InputFormat::Document,
+                    };
+                    let mut read = read.into_inner();
+                    let format = match format {
+                        InputFormat::Auto => {
+                            let (format, reason, sniffed) =
+                                InputFormat::detect(&mut read, CLI.input_buffer_size())?;
+                            info!(
+                                "Input {} detected as '{}': {}",
+                                i.or_untracked(),
+                                format,
+                                reason
                             );
-                            let mut index = i.as_ref().map(|_| 1);
-                            // Note that this is an Option inside an Option... the outside option controls when the loop should end (i.e EOF),
-                            // the inside option controls whether to store the data
-                            while let Some(slice) = line_reader.next_line().map(|res| {
-                                if CLI.should_calculate(Field::Value) {
-                                    Some(res)
-                                } else {
-                                    None
-                                }
-                            }) {
-                                if check_index(CLI.regex(), index)
-                                    && index
-                                        .as_ref()
-                                        .map_or(true, |i| *i >= CLI.line_start_number())
-                                {
-                                    debug!(
-                                        "Processing line {} of input {}...",
-                                        index.or_untracked(),
-                                        i.or_untracked()
-                                    );
-                                    let reader =
-                                        slice.transpose()?.map(|s| s.iter().map(|&b| Ok(b)));
-                                    unwind_json(&&CLI, index, reader, data_tx.clone())?;
-                                    index = index.map(|i| i + 1);
-                                } else {
-                                    debug!(
-                                        "Skipping line {} of input {}...",
-                                        index.or_untracked(),
-                                        i.or_untracked()
-                                    );
-                                    index = index.map(|i| i + 1);
-                                }
-                            }
+                            // The sniffed bytes are still part of the input
+                            read = Box::new(Cursor::new(sniffed).chain(read));
+                            format
                         }
-                        ((index, read), _) => {
-                            if check_index(CLI.regex(), index) {
-                                debug!("Processing input {}...", index.or_untracked());
-                                let reader = eval(
-                                    &Field::Value,
-                                    |b, (cap, read)| {
-                                        if b {
-                                            Some(BufReader::with_capacity(cap, read).bytes())
-                                        } else {
-                                            None
-                                        }
-                                    },
-                                    (CLI.input_buffer_size(), read.into_inner()),
-                                );
-                                unwind_json(&&CLI, index, reader, data_tx)?;
-                            } else {
-                                debug!("Skipping input {}...", index.or_untracked());
-                            }
+                        format => format,
+                    };
+                    let mut input = BufReader::with_capacity(CLI.input_buffer_size(), read);
+                    // Line delimited docs are independent, as are the subtrees
+                    // of split documents, and can be built in parallel
+                    let pooled = match format {
+                        InputFormat::Lines | InputFormat::RecordSeparated => CLI.build_jobs() > 1,
+                        _ => CLI.build_jobs() > 1 && CLI.split_depth().is_some(),
+                    };
+                    let (data_tx, data_rx) = syncQueue(CLI.channel_depth());
+                    m_chan_tx
+                        .send((data_rx, sink, pooled))
+                        .context(Context::umcc("Builder"))?;
+                    let mut data_tx = BatchSender::new(data_tx, CLI.batch_size());
+                    match format {
+                        InputFormat::Lines => {
+                            let eol = CLI.linereader_eol().clone();
+                            read_records(i, source, 0, eol, input, &mut data_tx)?
+                        }
+                        InputFormat::RecordSeparated => {
+                            // Drop everything up to and including the first separator,
+                            // so that each record is terminated by the next one's separator
+                            let skipped = input.read_until(RECORD_SEPARATOR, &mut Vec::new())?;
+                            let sep = RECORD_SEPARATOR.into();
+                            read_records(i, source, skipped as u64, sep, input, &mut data_tx)?
                         }
+                        InputFormat::Concatenated => {
+                            read_concatenated(i, source, input, &mut data_tx)?
+                        }
+                        // A top-level array of records is split into a doc per record
+                        InputFormat::Array => {
+                            let split = CLI.split_at().or(Some(&[]));
+                            read_document(i, source, split, input, &mut data_tx)?
+                        }
+                        _ => read_document(i, source, CLI.split_at(), input, &mut data_tx)?,
                     }
                 }
 
@@ -135,61 +255,393 @@ fn worker_reader(
         .map_err(|e| e.into())
 }
 
+/// Unwinds each record of a line (or otherwise separated) input
+/// as its own JSON doc, identified by its line number. 'skipped'
+/// is the number of bytes already read from input
+fn read_records<R: BufRead>(
+    ident: Option<usize>,
+    source: Option<Arc<str>>,
+    skipped: u64,
+    separator: RecordSeparator,
+    input: R,
+    channel: &mut ToBuilderTx,
+) -> Result<()> {
+    let mut line_reader = RecordReader::new(
+        separator,
+        CLI.input_buffer_size(),
+        CLI.input_buffer_max(),
+        input,
+    );
+    let mut index = ident.as_ref().map(|_| 1);
+    let mut line = 0;
+    loop {
+        let start = skipped + line_reader.position();
+        // Note that this is an Option inside an Option... the outside option controls when the loop should end (i.e EOF),
+        // the inside option controls whether to store the data
+        let slice =
+            match line_reader
+                .next_record()
+                .map(|res| if should_scan() { Some(res) } else { None })
+            {
+                Some(slice) => slice,
+                None => break,
+            };
+        line += 1;
+        if check_index(CLI.regex(), index)
+            && index
+                .as_ref()
+                .map_or(true, |i| *i >= CLI.line_start_number())
+        {
+            debug!(
+                "Processing line {} of input {}...",
+                index.or_untracked(),
+                ident.or_untracked()
+            );
+            let origin = source.as_ref().map(|source| {
+                let offset = Some(start).filter(|_| should_track_offset());
+                Origin::new(Arc::clone(source), Some(line), offset)
+            });
+            let source = slice.transpose()?;
+            unwind_json(
+                &&CLI,
+                index,
+                origin.as_ref(),
+                source,
+                CLI.split_at(),
+                // Split elements keep the line's ident
+                None,
+                channel,
+            )?;
+            index = index.map(|i| i + 1);
+        } else {
+            debug!(
+                "Skipping line {} of input {}...",
+                index.or_untracked(),
+                ident.or_untracked()
+            );
+            index = index.map(|i| i + 1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Unwinds each value of a concatenated input as its own
+/// JSON doc, identified by its position in the input
+fn read_concatenated<R: BufRead>(
+    ident: Option<usize>,
+    source: Option<Arc<str>>,
+    input: R,
+    channel: &mut ToBuilderTx,
+) -> Result<()> {
+    debug!("Processing input {}...", ident.or_untracked());
+    // Always scanned, as that's how the docs are told apart
+    let origin = source.map(|source| {
+        let offset = Some(0).filter(|_| should_track_offset());
+        Origin::new(source, None, offset)
+    });
+
+    unwind_concatenated(
+        &&CLI,
+        ident,
+        origin.as_ref(),
+        input,
+        CLI.split_at(),
+        channel,
+    )
+}
+
+/// Unwinds an entire input as a single JSON doc,
+/// or as one doc per element of the array at split, if any
+fn read_document<R: BufRead>(
+    ident: Option<usize>,
+    source: Option<Arc<str>>,
+    split: Option<&[String]>,
+    input: R,
+    channel: &mut ToBuilderTx,
+) -> Result<()> {
+    if check_index(CLI.regex(), ident) {
+        debug!("Processing input {}...", ident.or_untracked());
+        let reader = eval(
+            &Field::Value,
+            |b, input: R| {
+                if b || split.is_some() || should_scan() {
+                    Some(input)
+                } else {
+                    None
+                }
+            },
+            input,
+        );
+        let origin = source.map(|source| {
+            let offset = Some(0).filter(|_| should_track_offset());
+            Origin::new(source, None, offset)
+        });
+        let mut count = 0;
+        unwind_json(
+            &&CLI,
+            ident,
+            origin.as_ref(),
+            reader,
+            split,
+            Some(&mut count),
+            channel,
+        )
+    } else {
+        with_log!(Ok(()), debug!("Skipping input {}...", ident.or_untracked()))
+    }
+}
+
+/// Splitting needs the input scanned to find each doc, as do
+/// byte offsets, even if the value itself isn't output
+fn should_scan() -> bool {
+    CLI.should_calculate(Field::Value)
+        || CLI.should_calculate(Field::Raw)
+        || CLI.split_at().is_some()
+        || should_track_offset()
+}
+
+/// Checks if any field describing where a row came from is output,
+/// in which case each packet carries its origin to the builder
+fn should_track_origin() -> bool {
+    [Field::Source, Field::Line, Field::Offset, Field::Length]
+        .iter()
+        .any(|field| CLI.should_calculate(field))
+}
+
+fn should_track_offset() -> bool {
+    CLI.should_calculate(Field::Offset) || CLI.should_calculate(Field::Length)
+}
+
 /// Processes the intermediate data sent from the reader,
 /// producing and packaging output blocks based on runtime constraints
 fn worker_builder(
-    m_chan_rx: Receiver<Receiver<ToBuilder>>,
-    m_chan_tx: SyncSender<Receiver<ToWriter>>,
-    writer_h: WorkerHandle,
+    n: usize,
+    m_chan_rx: Receiver<(Receiver<Vec<ToBuilder>>, WriteSink, bool)>,
 ) -> Result<WorkerHandle> {
     Thread::new()
-        .name(format!("Builder"))
+        .name(format!("Builder-{}", n))
         .spawn(move || -> Result<()> {
             debug!("Builder initialized");
+            let pool = match CLI.build_jobs() {
+                1 => None,
+                size => Some(BuildPool::new(n, size)?),
+            };
             let result = || -> Result<()> {
                 // Hot loop
-                while let Some(channel) = m_chan_rx.iter().next() {
-                    let (data_tx, data_rx): (SyncSender<ToWriter>, Receiver<ToWriter>) =
-                        syncQueue(10);
-                    m_chan_tx.send(data_rx).context(Context::umcc("Writer"))?;
-
-                    for packet in channel.iter() {
-                        trace!(
-                            "Current packet is: {}, {:?}, {:?}",
-                            &packet.0.or_untracked(),
-                            &packet.1.or_untracked(),
-                            packet.2.as_ref().map(|vec| from_utf8(vec)).or_untracked()
-                        );
-                        let (json, ident, metadata) = JsonPacket::try_from(packet)?.into_inner();
-                        let builder = BlockGenerator::new(&CLI, json.as_ref(), metadata);
-
-                        for item in builder
-                            .map(|mut output| {
-                                output.store(&CLI, ident);
-                                output.store(&CLI, Some(CLI.delimiter()));
-                                output.store(&CLI, Some(CLI.guard()));
-                                output
-                            })
-                            .filter_map(|output| output.check(CLI.regex()))
-                        {
-                            trace!("Current in-processing output item is: {:?}", &item);
-                            data_tx.send(item.done()).context(Context::udcc())?;
+                while let Some((channel, sink, pooled)) = m_chan_rx.iter().next() {
+                    let (data_tx, deferred) = match sink {
+                        WriteSink::Ordered(data_tx) => (data_tx, None),
+                        WriteSink::Unordered(m_chan_tx) => {
+                            let (data_tx, data_rx) = queue();
+                            (DataSender::Unbounded(data_tx), Some((m_chan_tx, data_rx)))
                         }
-                        trace!("Finished processing a json pointer");
+                    };
+
+                    let mut data_tx = BatchSender::new(data_tx, CLI.batch_size());
+
+                    match pool.as_ref().filter(|_| pooled) {
+                        Some(pool) => pool.build_all(channel, &mut data_tx)?,
+                        None => {
+                            for batch in channel.iter() {
+                                for packet in batch {
+                                    build_packet(packet, |output| data_tx.send(output))?;
+                                }
+                                // Partial reader batches mark the end of a doc
+                                data_tx.flush()?;
+                            }
+                        }
+                    }
+                    // Completed inputs are handed to the writer, if it doesn't have them yet
+                    if let Some((m_chan_tx, data_rx)) = deferred {
+                        m_chan_tx.send(data_rx).context(Context::umcc("Writer"))?;
                     }
                     debug!("Finished processing a json document");
                 }
                 Ok(())
             };
             // Cleanup
-            match result() {
-                defer => thread_cleanup(defer, writer_h, m_chan_tx),
+            match result().and(pool.map_or(Ok(()), BuildPool::close)) {
+                Ok(_) => with_log!(Ok(()), info!("Builder-{} closing... success", n)),
+                Err(e) => with_log!(Err(e), warn!("Builder-{} closing... with error", n)),
             }
         })
         .map_err(|e| e.into())
 }
 
-fn worker_writer(m_chan_rx: Receiver<Receiver<ToWriter>>) -> Result<WorkerHandle> {
+/// Builds the output blocks of a single packet, handing each to emit
+fn build_packet<F>(packet: ToBuilder, mut emit: F) -> Result<()>
+where
+    F: FnMut(ToWriter) -> Result<()>,
+{
+    trace!(
+        "Current packet is: {}, {:?}, {:?}",
+        &packet.0.or_untracked(),
+        &packet.1.or_untracked(),
+        packet.2.as_ref().map(|vec| from_utf8(vec)).or_untracked()
+    );
+    let (json, ident, metadata, origin, span, subtrees) =
+        JsonPacket::try_from(packet)?.into_inner();
+    let builder = BlockGenerator::new(
+        &CLI,
+        json.as_ref(),
+        span.as_ref(),
+        subtrees.as_ref(),
+        metadata,
+    );
+
+    for item in builder
+        .map(|mut output| {
+            output.store(&CLI, ident);
+            output.store(&CLI, Some(CLI.delimiter()));
+            output.store(&CLI, Some(CLI.guard()));
+            output.store(&CLI, origin.as_ref().map(SourcePath::from));
+            output.store(&CLI, origin.as_ref().map(LineNumber::from));
+            output
+        })
+        .filter_map(|output| output.check(CLI.regex()))
+    {
+        trace!("Current in-processing output item is: {:?}", &item);
+        emit(item.done())?;
+    }
+    trace!("Finished processing a json pointer");
+
+    Ok(())
+}
+
+/// A pool of threads building packet batches in parallel. Each batch is tagged
+/// with a sequence number, and the results re-sequenced before being
+/// sent on, so output order matches that of a single builder
+/// (unless '--unordered' is set)
+struct BuildPool {
+    job_tx: SyncSender<PoolJob>,
+    result_rx: Receiver<(usize, Result<Vec<ToWriter>>)>,
+    handles: Vec<WorkerHandle>,
+}
+
+impl BuildPool {
+    fn new(n: usize, size: usize) -> Result<Self> {
+        let (job_tx, job_rx) = syncQueue(size);
+        let job_rx: Arc<Mutex<Receiver<PoolJob>>> = Arc::new(Mutex::new(job_rx));
+        // Unbounded, so that workers never wait on the builder
+        let (result_tx, result_rx) = queue();
+        let handles = (1..=size)
+            .map(|m| {
+                let job_rx = Arc::clone(&job_rx);
+                let result_tx: Sender<(usize, Result<Vec<ToWriter>>)> = result_tx.clone();
+                Thread::new()
+                    .name(format!("Builder-{}.{}", n, m))
+                    .spawn(move || -> Result<()> {
+                        loop {
+                            // See worker_reader, the lock is released before building
+                            let job = job_rx
+                                .lock()
+                                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                                .recv();
+                            let (seq, batch) = match job {
+                                Ok(job) => job,
+                                Err(_) => break Ok(()),
+                            };
+                            let mut outputs = Vec::new();
+                            let res = batch.into_iter().try_for_each(|packet| {
+                                build_packet(packet, |output| {
+                                    outputs.push(output);
+                                    Ok(())
+                                })
+                            });
+                            result_tx
+                                .send((seq, res.map(|_| outputs)))
+                                .context(Context::udcc())?;
+                        }
+                    })
+                    .map_err(|e| e.into())
+            })
+            .collect::<Result<Vec<WorkerHandle>>>()?;
+
+        Ok(BuildPool {
+            job_tx,
+            result_rx,
+            handles,
+        })
+    }
+
+    /// Builds every packet of an input, sending the results on in order
+    fn build_all(&self, channel: Receiver<Vec<ToBuilder>>, data_tx: &mut ToWriterTx) -> Result<()> {
+        // Bounds the number of batches waiting to be re-sequenced
+        let limit = self.handles.len() * 4;
+        let mut pending = BTreeMap::new();
+        let (mut sent, mut next) = (0usize, 0usize);
+
+        for batch in channel.iter() {
+            while sent - next >= limit {
+                let result = self
+                    .result_rx
+                    .recv()
+                    .map_err(|_| ErrorKind::ChannelError)
+                    .context(Context::udcc())?;
+                self.resequence(result, &mut pending, &mut next, data_tx)?;
+            }
+            self.job_tx.send((sent, batch)).context(Context::udcc())?;
+            sent += 1;
+            while let Ok(result) = self.result_rx.try_recv() {
+                self.resequence(result, &mut pending, &mut next, data_tx)?;
+            }
+        }
+        while next < sent {
+            let result = self
+                .result_rx
+                .recv()
+                .map_err(|_| ErrorKind::ChannelError)
+                .context(Context::udcc())?;
+            self.resequence(result, &mut pending, &mut next, data_tx)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores a result, sending on any that are now in sequence.
+    /// If the user doesn't care about order, results are sent immediately
+    fn resequence(
+        &self,
+        (seq, result): (usize, Result<Vec<ToWriter>>),
+        pending: &mut BTreeMap<usize, Vec<ToWriter>>,
+        next: &mut usize,
+        data_tx: &mut ToWriterTx,
+    ) -> Result<()> {
+        let seq = if CLI.unordered() { *next } else { seq };
+        pending.insert(seq, result?);
+        while let Some(outputs) = pending.remove(next) {
+            for output in outputs {
+                data_tx.send(output)?;
+            }
+            // Mirrors the reader's batches, see worker_builder
+            data_tx.flush()?;
+            *next += 1;
+        }
+
+        Ok(())
+    }
+
+    fn close(self) -> Result<()> {
+        drop(self.job_tx);
+        // Every worker is joined, keeping the first error (if any)
+        let mut joined = Ok(());
+        for handle in self.handles {
+            let res = match handle.join() {
+                Ok(inner) => inner,
+                Err(_) => Err(Error::from((
+                    ErrorKind::ThreadFailed,
+                    Context::tp("Builder"),
+                ))),
+            };
+            joined = joined.and(res);
+        }
+
+        joined
+    }
+}
+
+fn worker_writer(m_chan_rx: Receiver<Receiver<Vec<ToWriter>>>) -> Result<WorkerHandle> {
     Thread::new()
         .name(format!("Writer"))
         .spawn(move || -> Result<()> {
@@ -198,14 +650,49 @@ fn worker_writer(m_chan_rx: Receiver<Receiver<ToWriter>>) -> Result<WorkerHandle
             let mut writer =
                 BufWriter::with_capacity(opts.output_buffer_size(), get_writer(opts.writer()));
             info!("Buffered writer initialized");
+            let mut sorter = match opts.sort() {
+                true => Some(Sorter::new(opts.sort_buffer_size())),
+                false => None,
+            };
+            // Rows are numbered in the order they arrive, i.e before sorting
+            let mut row = 0;
+            let numbered = opts.should_store(Field::Row);
             let mut result = || -> Result<()> {
+                if opts.header() {
+                    write_header(&mut writer, opts.format(), &opts.guard(), &opts.delimiter())?;
+                }
                 // Hot loop
                 while let Some(channel) = m_chan_rx.iter().next() {
-                    for output in channel.iter() {
-                        write_formatted_output(&mut writer, output, opts.format())?;
+                    for mut output in channel.iter().flatten() {
+                        if numbered {
+                            row += 1;
+                            output.store_row(RowNumber::from(row));
+                            match opts.regex() {
+                                Some(regex)
+                                    if regex.is_row()
+                                        && !regex.pattern().is_match(&row.to_string()) =>
+                                {
+                                    continue
+                                }
+                                _ => {}
+                            }
+                        }
+                        match sorter {
+                            Some(ref mut sorter) => {
+                                let mut row = Vec::new();
+                                write_sort_key(&output, &mut row);
+                                let key = row.len();
+                                write_formatted_output(&mut row, output, opts.format())?;
+                                sorter.push(key, row)?;
+                            }
+                            None => write_formatted_output(&mut writer, output, opts.format())?,
+                        }
                     }
                     debug!("Write channel closing");
                 }
+                if let Some(sorter) = sorter.take() {
+                    sorter.finish(&mut writer)?;
+                }
                 Ok(())
             };
             // Cleanup